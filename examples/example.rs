@@ -1,5 +1,7 @@
 use playout::PlayoutModule;
 
+playout_macro::types!("./example.playout");
+
 fn main() {
     let playout_str = include_str!("./example.playout");
 
@@ -17,4 +19,14 @@ fn main() {
     use ash::vk;
     let out = playout_macro::layout!("./example.playout", 3);
     println!("{:#?}", out);
+    let flags = playout_macro::binding_flags!("./example.playout", 4);
+    println!(
+        "{:?} {:?}",
+        flags,
+        playout_macro::set_layout_flags!("./example.playout", 4)
+    );
+    println!("{:?} = {}", Face::NegY, Face::NegY as u32);
+    let mut packed = SpatialHashEntryPacked::default();
+    packed.set_sample_count(3);
+    println!("{:?} sample_count = {}", packed, packed.sample_count());
 }
@@ -1,31 +1,206 @@
 #![feature(proc_macro_span)]
 #![feature(alloc_layout_extra)]
 
+mod rust;
 #[cfg(feature = "vulkan")]
 mod vk;
 #[cfg(feature = "vulkan")]
 mod write;
 
-use playout::PlayoutModule;
+use playout::{PlayoutModule, SetLayout};
 use quote::quote;
 
 #[proc_macro]
 pub fn layout(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let mut input = input.into_iter();
-    let Some(token) = input.next() else {
+    let (module, set_id) = match parse_layout_args(input) {
+        Ok(args) => args,
+        Err(err) => return err.into(),
+    };
+    if let Some(set_id) = set_id {
+        match find_set(&module, set_id) {
+            Ok(set) if set.bindings.iter().any(|b| b.immutable_sampler.is_some()) => {
+                let message = format!(
+                    "Set {set_id} has immutable samplers, create it with create_set_layout!"
+                );
+                quote! {
+                    compile_error!(#message)
+                }
+                .into()
+            }
+            Ok(set) => vk::set_layout_to_vk(&module, set).into(),
+            Err(err) => err.into(),
+        }
+    } else {
+        vk::push_constant_layout_to_vk(&module).into()
+    }
+}
+
+/// `[vk::DescriptorBindingFlags; N]` for the bindings of a set, in the order of `layout!`.
+#[proc_macro]
+pub fn binding_flags(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (module, set_id) = match parse_layout_args(input) {
+        Ok(args) => args,
+        Err(err) => return err.into(),
+    };
+    let Some(set_id) = set_id else {
         return quote! {
-            compile_error!("Expects path to .playout file")
+            compile_error!("Push constants have no binding flags")
         }
         .into();
     };
+    match find_set(&module, set_id) {
+        Ok(set) => vk::binding_flags_to_vk(set).into(),
+        Err(err) => err.into(),
+    }
+}
+
+/// `[vk::MutableDescriptorTypeListEXT; N]` for `vk::MutableDescriptorTypeCreateInfoEXT`, in the
+/// order of `layout!`.
+#[proc_macro]
+pub fn mutable_descriptor_types(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (module, set_id) = match parse_layout_args(input) {
+        Ok(args) => args,
+        Err(err) => return err.into(),
+    };
+    let Some(set_id) = set_id else {
+        return quote! {
+            compile_error!("Push constants have no mutable descriptor types")
+        }
+        .into();
+    };
+    match find_set(&module, set_id) {
+        Ok(set) => vk::mutable_descriptor_types_to_vk(set).into(),
+        Err(err) => err.into(),
+    }
+}
+
+/// `vk::DescriptorSetLayoutCreateFlags` required by the bindings of a set.
+#[proc_macro]
+pub fn set_layout_flags(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (module, set_id) = match parse_layout_args(input) {
+        Ok(args) => args,
+        Err(err) => return err.into(),
+    };
+    let Some(set_id) = set_id else {
+        return quote! {
+            compile_error!("Push constants have no set layout flags")
+        }
+        .into();
+    };
+    match find_set(&module, set_id) {
+        Ok(set) => vk::set_layout_flags_to_vk(set).into(),
+        Err(err) => err.into(),
+    }
+}
+
+/// `[vk::SamplerCreateInfo; N]` for the samplers declared in a playout file, ordered by name.
+#[proc_macro]
+pub fn samplers(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+    let module = match load_module(&path.value(), path.span().unwrap()) {
+        Ok(module) => module,
+        Err(err) => return err.into(),
+    };
+    vk::samplers_to_vk(&module).into()
+}
+
+struct CreateSetLayoutArgs {
+    path: syn::LitStr,
+    set_id: syn::LitInt,
+    device: syn::Expr,
+}
+
+impl syn::parse::Parse for CreateSetLayoutArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let _comma: syn::Token![,] = input.parse()?;
+        let set_id = input.parse()?;
+        let _comma: syn::Token![,] = input.parse()?;
+        let device = input.parse()?;
+        Ok(Self {
+            path,
+            set_id,
+            device,
+        })
+    }
+}
+
+/// `create_set_layout!("file", set, &device)` creates the immutable samplers of a set and the
+/// set layout, chaining the binding flags. Evaluates to
+/// `Result<(vk::DescriptorSetLayout, Vec<vk::Sampler>), vk::Result>` and must be used in an
+/// unsafe block.
+#[proc_macro]
+pub fn create_set_layout(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(input as CreateSetLayoutArgs);
+    let module = match load_module(&args.path.value(), args.path.span().unwrap()) {
+        Ok(module) => module,
+        Err(err) => return err.into(),
+    };
+    let set_id = match args.set_id.base10_parse::<u32>() {
+        Ok(set_id) => set_id,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    match find_set(&module, set_id) {
+        Ok(set) => vk::create_set_layout_to_vk(&module, set, &args.device).into(),
+        Err(err) => err.into(),
+    }
+}
+
+struct UpdateTemplateArgs {
+    path: syn::LitStr,
+    set: syn::Ident,
+}
+
+impl syn::parse::Parse for UpdateTemplateArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let _comma: syn::Token![,] = input.parse()?;
+        let set = input.parse()?;
+        Ok(Self { path, set })
+    }
+}
+
+/// `update_template!("file", SetName)` declares `SetNameDescriptors`, a struct of the descriptors
+/// of a set laid out for `vk::DescriptorUpdateTemplate`, with its entries in
+/// `SetNameDescriptors::ENTRIES`. Mutable bindings have no concrete descriptor type to write
+/// through the template, and variable count bindings no fixed count, so both are left out of the
+/// struct.
+#[proc_macro]
+pub fn update_template(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(input as UpdateTemplateArgs);
+    let module = match load_module(&args.path.value(), args.path.span().unwrap()) {
+        Ok(module) => module,
+        Err(err) => return err.into(),
+    };
+    let Some(set) = module
+        .descriptor_sets
+        .iter()
+        .find(|set| args.set == set.name)
+    else {
+        return syn::Error::new(args.set.span(), "Descriptor set not found in playout file")
+            .to_compile_error()
+            .into();
+    };
+    vk::update_template_to_vk(&module, set).into()
+}
+
+/// Parse `"path", set_id` or `"path", "push"` and load the module.
+fn parse_layout_args(
+    input: proc_macro::TokenStream,
+) -> Result<(PlayoutModule, Option<u32>), proc_macro2::TokenStream> {
+    let mut input = input.into_iter();
+    let Some(token) = input.next() else {
+        return Err(quote! {
+            compile_error!("Expects path to .playout file")
+        });
+    };
 
     match input.next() {
         Some(proc_macro::TokenTree::Punct(punct)) if punct.as_char() == ',' => (),
         _ => {
-            return quote! {
+            return Err(quote! {
                 compile_error!("Expects comma")
-            }
-            .into()
+            })
         }
     }
 
@@ -35,72 +210,106 @@ pub fn layout(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             if let Ok(set_id) = lit.to_string().parse::<u32>() {
                 Some(set_id)
             } else {
-                return quote! {
+                return Err(quote! {
                     compile_error!("Expects integer literal for set id")
-                }
-                .into();
+                });
             }
         }
         Some(proc_macro::TokenTree::Ident(ident)) if ident.to_string() == "\"push\"" => None,
         _ => {
-            return quote! {
+            return Err(quote! {
                 compile_error!("Expects set id or push")
-            }
-            .into()
+            })
         }
     };
 
     if input.next().is_some() {
-        return quote! {
+        return Err(quote! {
             compile_error!("Expects exactly one string literal as input")
-        }
-        .into();
+        });
     }
-    let path = token.to_string();
-    let path = path.strip_prefix('"').unwrap().strip_suffix('"').unwrap();
-    let path = token
-        .span()
-        .source_file()
-        .path()
-        .parent()
-        .unwrap()
-        .join(path);
+    let module = load_module(&token.to_string(), token.span())?;
+    Ok((module, set_id))
+}
+
+fn find_set(module: &PlayoutModule, set_id: u32) -> Result<&SetLayout, proc_macro2::TokenStream> {
+    module
+        .descriptor_sets
+        .iter()
+        .find(|set| set.set == set_id)
+        .ok_or_else(|| {
+            let missing_id = format!("Set id {set_id} does not exist within this playout file");
+            quote! {
+                compile_error!(#missing_id)
+            }
+        })
+}
+
+#[proc_macro]
+pub fn write(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(tokens as write::DescriptorSetWriteArgs);
+    input.into_vk().into()
+}
+
+#[proc_macro]
+pub fn types(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+    let module = match load_module(&path.value(), path.span().unwrap()) {
+        Ok(module) => module,
+        Err(err) => return err.into(),
+    };
+    rust::module_to_rust(&module).into()
+}
+
+/// The `playout::DeviceFeatures` required by a playout file, as a constant expression.
+#[proc_macro]
+pub fn required_features(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+    let module = match load_module(&path.value(), path.span().unwrap()) {
+        Ok(module) => module,
+        Err(err) => return err.into(),
+    };
+    let bits = proc_macro2::Literal::u64_suffixed(module.required_features().bits());
+    quote!(::playout::DeviceFeatures::from_bits_retain(#bits)).into()
+}
+
+/// `[&CStr; N]` with the device extensions required by a playout file.
+#[proc_macro]
+pub fn required_extensions(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = syn::parse_macro_input!(input as syn::LitStr);
+    let module = match load_module(&path.value(), path.span().unwrap()) {
+        Ok(module) => module,
+        Err(err) => return err.into(),
+    };
+    let extensions = module.required_extensions().into_iter().map(|extension| {
+        proc_macro2::Literal::c_string(&std::ffi::CString::new(extension).unwrap())
+    });
+    quote!([#(#extensions),*]).into()
+}
+
+/// Load and parse a .playout file relative to the source file containing `span`.
+fn load_module(
+    path: &str,
+    span: proc_macro::Span,
+) -> Result<PlayoutModule, proc_macro2::TokenStream> {
+    let path = path.trim_start_matches('"').trim_end_matches('"');
+    let path = span.source_file().path().parent().unwrap().join(path);
     let file = match std::fs::read_to_string(path) {
         Ok(file) => file,
         Err(err) => {
             let message = err.to_string();
-            return quote! {
+            return Err(quote! {
                 compile_error!(#message)
-            }
-            .into();
+            });
         }
     };
-    let module = match PlayoutModule::try_from(file.as_str()) {
-        Ok(module) => module,
+    match PlayoutModule::try_from(file.as_str()) {
+        Ok(module) => Ok(module),
         Err(err) => {
             let message = err.to_compile_error();
-            return quote! {
+            Err(quote! {
                 #message
-            }
-            .into();
+            })
         }
-    };
-    if let Some(set_id) = set_id {
-        let Some(set) = module.descriptor_sets.iter().find(|set| set.set == set_id) else {
-            let missing_id = format!("Set id {set_id} does not exist within this playout file");
-            return quote! {
-                compile_error!(#missing_id)
-            }
-            .into();
-        };
-        vk::set_layout_to_vk(&module, set).into()
-    } else {
-        vk::push_constant_layout_to_vk(&module).into()
     }
 }
-
-#[proc_macro]
-pub fn write(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = syn::parse_macro_input!(tokens as write::DescriptorSetWriteArgs);
-    input.into_vk().into()
-}
@@ -0,0 +1,360 @@
+use playout::{
+    BlockLayout, DataEnum, DataStruct, Field, MatrixLayout, PlayoutModule, PrimitiveType,
+    PrimitiveTypeSingle, Type, TypeAlias,
+};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use std::collections::BTreeSet;
+
+pub fn module_to_rust(module: &PlayoutModule) -> TokenStream {
+    let errors = layout_mismatches(module).into_iter().map(|message| {
+        quote! {
+            compile_error!(#message);
+        }
+    });
+    let enums = module.data_enums.values().map(data_enum_to_rust);
+    let bitfields = module
+        .data_structs
+        .values()
+        .flat_map(|data_struct| {
+            data_struct
+                .fields
+                .iter()
+                .map(|field| (data_struct.ident.as_str(), field))
+        })
+        .chain(
+            module
+                .push_constants
+                .fields
+                .iter()
+                .map(|field| (module.push_constants.name.as_str(), &field.field)),
+        )
+        .filter_map(|(owner, field)| bitfield_to_rust(owner, field));
+    let aliases = module
+        .type_aliases
+        .values()
+        .map(|alias| type_alias_to_rust(module, alias));
+    let structs = module
+        .data_structs
+        .values()
+        .map(|data_struct| data_struct_to_rust(module, data_struct));
+    quote! {
+        #(#errors)*
+        #(#enums)*
+        #(#bitfields)*
+        #(#aliases)*
+        #(#structs)*
+    }
+}
+
+fn data_struct_to_rust(module: &PlayoutModule, data_struct: &DataStruct) -> TokenStream {
+    let ident = syn::Ident::new(&data_struct.ident, Span::call_site());
+    let layout = data_struct
+        .layout_in(module, BlockLayout::default(), MatrixLayout::default())
+        .unwrap();
+    let unsized_struct = matches!(
+        data_struct.fields.last().map(|field| &field.ty),
+        Some(Type::Slice { .. })
+    );
+    // Explicit padding so that the Rust struct matches the std430 offsets.
+    let mut fields = Vec::new();
+    let mut end = 0;
+    let padding = |fields: &mut Vec<TokenStream>, size: usize| {
+        let name = syn::Ident::new(&format!("_padding{}", fields.len()), Span::call_site());
+        fields.push(quote! {
+            pub #name: [u8; #size]
+        });
+    };
+    for (field, offset) in data_struct.fields.iter().zip(layout.offsets.iter()) {
+        if *offset > end {
+            padding(&mut fields, *offset - end);
+        }
+        let major = field.matrix_layout.unwrap_or_default();
+        end = offset
+            + field
+                .ty
+                .layout_in(module, BlockLayout::default(), major)
+                .unwrap()
+                .size();
+        let name = syn::Ident::new(field.ident.as_ref().unwrap(), Span::call_site());
+        let ty = field_type_to_rust(module, &data_struct.ident, field);
+        fields.push(quote! {
+            pub #name: #ty
+        });
+    }
+    if !unsized_struct && layout.layout.size() > end {
+        padding(&mut fields, layout.layout.size() - end);
+    }
+    // Structs ending with a runtime sized array are unsized and can't be copied.
+    let derives = if unsized_struct {
+        quote!(#[derive(Debug)])
+    } else {
+        quote!(#[derive(Debug, Clone, Copy)])
+    };
+    let repr = match data_struct.align {
+        Some(align) => {
+            let align = Literal::usize_unsuffixed(align);
+            quote!(#[repr(C, align(#align))])
+        }
+        None => quote!(#[repr(C)]),
+    };
+    quote! {
+        #repr
+        #derives
+        pub struct #ident {
+            #(#fields),*
+        }
+    }
+}
+
+fn field_type_to_rust(module: &PlayoutModule, owner: &str, field: &Field) -> TokenStream {
+    match &field.ty {
+        Type::Bitfield { .. } => {
+            let ident = bitfield_type_name(owner, field.ident.as_ref().unwrap());
+            quote!(#ident)
+        }
+        ty => type_to_rust(module, ty, field.matrix_layout.unwrap_or_default()),
+    }
+}
+
+fn type_alias_to_rust(module: &PlayoutModule, alias: &TypeAlias) -> TokenStream {
+    let ident = syn::Ident::new(&alias.ident, Span::call_site());
+    let ty = type_to_rust(module, &alias.ty, MatrixLayout::default());
+    quote! {
+        pub type #ident = #ty;
+    }
+}
+
+pub(crate) fn type_to_rust(module: &PlayoutModule, ty: &Type, major: MatrixLayout) -> TokenStream {
+    match ty {
+        Type::Array { ty, size } => {
+            let ty = array_element_to_rust(module, ty, major);
+            quote!([#ty; #size])
+        }
+        Type::Primitive(ty) => primitive_type_to_rust(ty, major),
+        Type::Path(path) => {
+            let ident = syn::Ident::new(path, Span::call_site());
+            quote!(#ident)
+        }
+        Type::Slice { ty } => {
+            let ty = array_element_to_rust(module, ty, major);
+            quote!([#ty])
+        }
+        Type::Bitfield { ty, .. } => primitive_type_single_to_rust(ty),
+        Type::Ptr { ty } => {
+            let ty = type_to_rust(module, ty, major);
+            quote!(::playout::DevicePtr<#ty>)
+        }
+    }
+}
+
+/// Array elements padded to their std430 array stride. Three-component vectors are the only
+/// types whose size isn't a multiple of their alignment, so they take a fourth component.
+fn array_element_to_rust(module: &PlayoutModule, ty: &Type, major: MatrixLayout) -> TokenStream {
+    match ty.resolve(module) {
+        Type::Primitive(PrimitiveType::Vec { ty, length: 3 }) => {
+            let ty = primitive_type_single_to_rust(ty);
+            quote!([#ty; 4])
+        }
+        _ => type_to_rust(module, ty, major),
+    }
+}
+
+/// Generated structs follow the std430 layout with the matrix layouts of their own fields.
+/// Blocks laying them out differently, under std140 or scalar or with an inherited matrix
+/// layout, can't use them.
+fn layout_mismatches(module: &PlayoutModule) -> Vec<String> {
+    let mut mismatches = BTreeSet::new();
+    for set in module.descriptor_sets.iter() {
+        for binding in set.bindings.iter() {
+            for member in binding.declared_types() {
+                if let Some(ty) = member.block_type() {
+                    let block = binding.block_layout_of(member);
+                    let location = format!("`{}.{}` ({})", set.name, binding.ident, block.name());
+                    check_layout(
+                        module,
+                        ty,
+                        block,
+                        MatrixLayout::default(),
+                        &location,
+                        &mut mismatches,
+                    );
+                }
+            }
+        }
+    }
+    for field in module.push_constants.fields.iter() {
+        let location = format!("`{}` (std430)", module.push_constants.name);
+        let major = field.field.matrix_layout.unwrap_or_default();
+        check_layout(
+            module,
+            &field.field.ty,
+            BlockLayout::default(),
+            major,
+            &location,
+            &mut mismatches,
+        );
+    }
+    mismatches.into_iter().collect()
+}
+
+fn check_layout(
+    module: &PlayoutModule,
+    ty: &Type,
+    block: BlockLayout,
+    major: MatrixLayout,
+    location: &str,
+    mismatches: &mut BTreeSet<String>,
+) {
+    let data_struct = match ty.resolve(module) {
+        Type::Array { ty, .. } | Type::Slice { ty } => {
+            return check_layout(module, ty, block, major, location, mismatches)
+        }
+        Type::Path(path) => match module.data_structs.get(path) {
+            Some(data_struct) => data_struct,
+            None => return,
+        },
+        _ => return,
+    };
+    let (Ok(layout), Ok(rust_layout)) = (
+        data_struct.layout_in(module, block, major),
+        data_struct.layout_in(module, BlockLayout::default(), MatrixLayout::default()),
+    ) else {
+        return;
+    };
+    let implicit_matrices_differ = data_struct.fields.iter().any(|field| {
+        field.matrix_layout.is_none()
+            && field.ty.matrix_layout_in(module, major).ok()
+                != field
+                    .ty
+                    .matrix_layout_in(module, MatrixLayout::default())
+                    .ok()
+    });
+    if layout.offsets != rust_layout.offsets
+        || layout.layout.size() != rust_layout.layout.size()
+        || implicit_matrices_differ
+    {
+        mismatches.insert(format!(
+            "Struct `{}` is laid out differently in {} than its generated std430 Rust struct",
+            data_struct.ident, location
+        ));
+        return;
+    }
+    for field in data_struct.fields.iter() {
+        let major = field.matrix_layout.unwrap_or(major);
+        check_layout(module, &field.ty, block, major, location, mismatches);
+    }
+}
+
+pub(crate) fn primitive_type_to_rust(ty: &PrimitiveType, major: MatrixLayout) -> TokenStream {
+    match ty {
+        PrimitiveType::Single(ty) => primitive_type_single_to_rust(ty),
+        PrimitiveType::Vec { ty, length } => {
+            let ty = primitive_type_single_to_rust(ty);
+            let length = *length as usize;
+            quote!([#ty; #length])
+        }
+        PrimitiveType::Mat {
+            ty: component,
+            rows,
+            columns,
+        } => {
+            // Array of columns or rows, each padded to the matrix stride.
+            let vectors = match major {
+                MatrixLayout::ColumnMajor => *columns as usize,
+                MatrixLayout::RowMajor => *rows as usize,
+            };
+            let components = ty.matrix_stride(BlockLayout::default(), major).unwrap()
+                / component.layout().size();
+            let component = primitive_type_single_to_rust(component);
+            quote!([[#component; #components]; #vectors])
+        }
+    }
+}
+
+/// The name of the newtype generated for bitfield `field` on struct `owner`.
+pub(crate) fn bitfield_type_name(owner: &str, field: &str) -> syn::Ident {
+    let mut name = owner.to_string();
+    for word in field.split('_') {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.extend(chars);
+        }
+    }
+    syn::Ident::new(&name, Span::call_site())
+}
+
+fn bitfield_to_rust(owner: &str, field: &Field) -> Option<TokenStream> {
+    let Type::Bitfield { ty, members } = &field.ty else {
+        return None;
+    };
+    let ident = bitfield_type_name(owner, field.ident.as_ref().unwrap());
+    let repr = primitive_type_single_to_rust(ty);
+    let literal = |value: u64| match ty {
+        PrimitiveTypeSingle::U8 => Literal::u8_suffixed(value as u8),
+        PrimitiveTypeSingle::U16 => Literal::u16_suffixed(value as u16),
+        _ => Literal::u32_suffixed(value as u32),
+    };
+    let accessors = members.iter().map(|member| {
+        let getter = syn::Ident::new(&member.ident, Span::call_site());
+        let setter = syn::Ident::new(&format!("set_{}", member.ident), Span::call_site());
+        let offset = Literal::u32_suffixed(member.offset);
+        let mask = literal(member.mask());
+        let clear = literal(!(member.mask() << member.offset));
+        quote! {
+            pub const fn #getter(self) -> #repr {
+                (self.0 >> #offset) & #mask
+            }
+            pub fn #setter(&mut self, value: #repr) {
+                self.0 = (self.0 & #clear) | ((value & #mask) << #offset);
+            }
+        }
+    });
+    Some(quote! {
+        #[repr(transparent)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct #ident(pub #repr);
+        impl #ident {
+            #(#accessors)*
+        }
+    })
+}
+
+fn data_enum_to_rust(data_enum: &DataEnum) -> TokenStream {
+    let ident = syn::Ident::new(&data_enum.ident, Span::call_site());
+    let repr = primitive_type_single_to_rust(&data_enum.ty);
+    let variants = data_enum.variants.iter().map(|variant| {
+        let ident = syn::Ident::new(&variant.ident, Span::call_site());
+        let value = Literal::i64_unsuffixed(variant.value);
+        quote! {
+            #ident = #value
+        }
+    });
+    quote! {
+        #[repr(#repr)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum #ident {
+            #(#variants),*
+        }
+    }
+}
+
+pub(crate) fn primitive_type_single_to_rust(ty: &PrimitiveTypeSingle) -> TokenStream {
+    match ty {
+        PrimitiveTypeSingle::U8 => quote!(u8),
+        PrimitiveTypeSingle::U16 => quote!(u16),
+        PrimitiveTypeSingle::U32 => quote!(u32),
+        PrimitiveTypeSingle::U64 => quote!(u64),
+        PrimitiveTypeSingle::I8 => quote!(i8),
+        PrimitiveTypeSingle::I16 => quote!(i16),
+        PrimitiveTypeSingle::I32 => quote!(i32),
+        PrimitiveTypeSingle::I64 => quote!(i64),
+        // Rust has no stable f16. Expose the raw bits instead.
+        PrimitiveTypeSingle::F16 => quote!(u16),
+        PrimitiveTypeSingle::F32 => quote!(f32),
+        PrimitiveTypeSingle::F64 => quote!(f64),
+        // Booleans are 32 bits wide in shader interfaces
+        PrimitiveTypeSingle::Bool => quote!(u32),
+    }
+}
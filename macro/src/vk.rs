@@ -1,24 +1,36 @@
-use playout::{Binding, DescriptorType, PlayoutModule, SetLayout, ShaderStages};
+use playout::{
+    Binding, BindingFlags, BlockLayout, DescriptorType, ImmutableSampler, MatrixLayout,
+    PlayoutModule, SamplerFieldValue, SetLayout, ShaderStages,
+};
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 
 pub fn push_constant_layout_to_vk(module: &PlayoutModule) -> TokenStream {
-    let mut ranges: Vec<(ShaderStages, u32, u32)> = Vec::new(); // stage, start, size
-    let mut current_stage_flags = ShaderStages::empty();
-    for field in module.push_constants.fields.iter() {
-        if field.stages != current_stage_flags {
-            let start = ranges.last().map(|a| a.1 + a.2).unwrap_or(0);
-            ranges.push((field.stages, start, 0));
-            current_stage_flags = field.stages;
-        } else {
-            let last = ranges.last_mut().unwrap();
-            let size = field.field.ty.layout(module).pad_to_align().size();
-            last.2 += size as u32; // TODO: should be sizeof(T)
+    let mut ranges: Vec<(ShaderStages, usize, usize)> = Vec::new(); // stage, start, end
+    let layout = module
+        .push_constants
+        .layout_in(module, BlockLayout::default())
+        .unwrap();
+    for (field, offset) in module.push_constants.fields.iter().zip(layout.offsets) {
+        let major = field.field.matrix_layout.unwrap_or_default();
+        let end = offset
+            + field
+                .field
+                .ty
+                .layout_in(module, BlockLayout::default(), major)
+                .unwrap()
+                .size();
+        match ranges.last_mut() {
+            Some(last) if last.0 == field.stages => last.2 = end,
+            _ => ranges.push((field.stages, offset, end)),
         }
     }
 
-    let ranges = ranges.iter().map(|(stage, start, size)| {
+    let ranges = ranges.iter().map(|(stage, start, end)| {
         let stage = stage_flag_to_vk(stage);
+        // Push constant ranges must start and end on multiples of 4 bytes.
+        let start = (start / 4 * 4) as u32;
+        let size = end.next_multiple_of(4) as u32 - start;
         quote! {
             vk::PushConstantRange {
                 stage_flags: #stage,
@@ -37,21 +49,366 @@ pub fn set_layout_to_vk(module: &PlayoutModule, layout: &SetLayout) -> TokenStre
     let bindings = layout
         .bindings
         .iter()
-        .map(|binding| binding_to_vk(module, binding));
+        .map(|binding| binding_to_vk(module, binding, quote!(::std::ptr::null())));
     quote! {[
         #(#bindings),*
     ]}
 }
 
+/// Creates the immutable samplers of a set and the set layout using them, returning
+/// `Result<(vk::DescriptorSetLayout, Vec<vk::Sampler>), vk::Result>`.
+pub fn create_set_layout_to_vk(
+    module: &PlayoutModule,
+    layout: &SetLayout,
+    device: &syn::Expr,
+) -> TokenStream {
+    // Each sampler is created once, even when shared by several bindings.
+    let mut sampler_names: Vec<&str> = Vec::new();
+    for binding in layout.bindings.iter() {
+        if let Some(name) = &binding.immutable_sampler {
+            if !sampler_names.contains(&name.as_str()) {
+                sampler_names.push(name);
+            }
+        }
+    }
+    let sampler_count = sampler_names.len();
+    let sampler_infos = sampler_names
+        .iter()
+        .map(|name| sampler_to_vk(&module.samplers[*name]));
+
+    let mut immutable_samplers = Vec::new();
+    let bindings = layout.bindings.iter().map(|binding| {
+        let Some(name) = &binding.immutable_sampler else {
+            return binding_to_vk(module, binding, quote!(::std::ptr::null()));
+        };
+        let index = sampler_names.iter().position(|n| n == name).unwrap();
+        let count = binding.descriptor_count as usize;
+        let ident = syn::Ident::new(
+            &format!("immutable_samplers_{}", binding.binding),
+            Span::call_site(),
+        );
+        immutable_samplers.push(quote! {
+            let #ident = [samplers[#index]; #count];
+        });
+        binding_to_vk(module, binding, quote!(#ident.as_ptr()))
+    });
+    let bindings = bindings.collect::<Vec<_>>();
+
+    let flags = set_layout_flags_to_vk(layout);
+    let binding_flags = if layout
+        .bindings
+        .iter()
+        .any(|binding| !binding.flags.is_empty())
+    {
+        let binding_flags = binding_flags_to_vk(layout);
+        quote! {
+            let binding_flags = #binding_flags;
+            let mut binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::default()
+                .binding_flags(&binding_flags);
+            let info = info.push_next(&mut binding_flags_info);
+        }
+    } else {
+        quote!()
+    };
+    let mutable_types = if layout
+        .bindings
+        .iter()
+        .any(|binding| matches!(binding.descriptor_type, DescriptorType::Mutable { .. }))
+    {
+        let mutable_types = mutable_descriptor_types_to_vk(layout);
+        quote! {
+            let mutable_types = #mutable_types;
+            let mut mutable_types_info = vk::MutableDescriptorTypeCreateInfoEXT::default()
+                .mutable_descriptor_type_lists(&mutable_types);
+            let info = info.push_next(&mut mutable_types_info);
+        }
+    } else {
+        quote!()
+    };
+    quote! {
+        (|| -> ::std::result::Result<(vk::DescriptorSetLayout, Vec<vk::Sampler>), vk::Result> {
+            let device = #device;
+            let sampler_infos: [vk::SamplerCreateInfo; #sampler_count] = [#(#sampler_infos),*];
+            let mut samplers = Vec::with_capacity(sampler_infos.len());
+            for info in sampler_infos.iter() {
+                match device.create_sampler(info, None) {
+                    Ok(sampler) => samplers.push(sampler),
+                    Err(err) => {
+                        for sampler in samplers {
+                            device.destroy_sampler(sampler, None);
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+            #(#immutable_samplers)*
+            let bindings = [#(#bindings),*];
+            let info = vk::DescriptorSetLayoutCreateInfo::default()
+                .flags(#flags)
+                .bindings(&bindings);
+            #binding_flags
+            #mutable_types
+            match device.create_descriptor_set_layout(&info, None) {
+                Ok(layout) => Ok((layout, samplers)),
+                Err(err) => {
+                    for sampler in samplers {
+                        device.destroy_sampler(sampler, None);
+                    }
+                    Err(err)
+                }
+            }
+        })()
+    }
+}
+
+/// A `#[repr(C)]` struct holding the descriptors of a set, with the
+/// `vk::DescriptorUpdateTemplateEntry` array pointing into it. Mutable and variable count
+/// bindings are left out and listed in the doc comment of the struct.
+pub fn update_template_to_vk(module: &PlayoutModule, layout: &SetLayout) -> TokenStream {
+    let struct_name = syn::Ident::new(&format!("{}Descriptors", layout.name), Span::call_site());
+    let mut skipped = Vec::new();
+    let mut fields = Vec::new();
+    let mut defaults = Vec::new();
+    let mut entries = Vec::new();
+    for binding in layout.bindings.iter() {
+        if binding.ident == "_" {
+            continue;
+        }
+        if matches!(binding.descriptor_type, DescriptorType::Sampler { .. })
+            && binding.immutable_sampler.is_some()
+        {
+            // Immutable samplers are never written.
+            continue;
+        }
+        if matches!(binding.descriptor_type, DescriptorType::Mutable { .. }) {
+            // Template entries need the concrete type of the descriptors they write.
+            skipped.push(binding.ident.as_str());
+            continue;
+        }
+        if binding
+            .flags
+            .contains(BindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+        {
+            // The count is chosen when allocating the set, and writing the maximum count would
+            // overrun sets allocated with fewer descriptors.
+            skipped.push(binding.ident.as_str());
+            continue;
+        }
+        let ident = syn::Ident::new(&binding.ident, Span::call_site());
+        let (ty, count) = match &binding.descriptor_type {
+            DescriptorType::Sampler { .. }
+            | DescriptorType::CombinedImageSampler { .. }
+            | DescriptorType::SampledImage { .. }
+            | DescriptorType::StorageImage { .. }
+            | DescriptorType::InputAttachment { .. } => {
+                (quote!(vk::DescriptorImageInfo), binding.descriptor_count)
+            }
+            DescriptorType::UniformTexelBuffer { .. }
+            | DescriptorType::StorageTexelBuffer { .. } => {
+                (quote!(vk::BufferView), binding.descriptor_count)
+            }
+            DescriptorType::UniformBuffer { .. }
+            | DescriptorType::StorageBuffer { .. }
+            | DescriptorType::UniformBufferDynamic { .. }
+            | DescriptorType::StorageBufferDynamic { .. } => {
+                (quote!(vk::DescriptorBufferInfo), binding.descriptor_count)
+            }
+            DescriptorType::AccelerationStructure => (
+                quote!(vk::AccelerationStructureKHR),
+                binding.descriptor_count,
+            ),
+            DescriptorType::Mutable { .. } => unreachable!(),
+            DescriptorType::InlineUniformBlock { ty } => {
+                // The descriptor count of inline uniform blocks is their size in bytes.
+                let layout =
+                    match ty.layout_in(module, binding.block_layout(), MatrixLayout::default()) {
+                        Ok(layout) => layout,
+                        Err(err) => {
+                            let message = format!("Binding {}: {}", binding.ident, err);
+                            return quote! {
+                                compile_error!(#message);
+                            };
+                        }
+                    };
+                (quote!(u8), layout.size() as u32)
+            }
+        };
+        let stride = if matches!(
+            binding.descriptor_type,
+            DescriptorType::InlineUniformBlock { .. }
+        ) {
+            quote!(0)
+        } else {
+            quote!(::std::mem::size_of::<#ty>())
+        };
+        let is_array = binding.descriptor_count > 1
+            || matches!(
+                binding.descriptor_type,
+                DescriptorType::InlineUniformBlock { .. }
+            );
+        if is_array {
+            let count = count as usize;
+            fields.push(quote!(pub #ident: [#ty; #count]));
+            defaults.push(quote!(#ident: [Default::default(); #count]));
+        } else {
+            fields.push(quote!(pub #ident: #ty));
+            defaults.push(quote!(#ident: Default::default()));
+        }
+        let binding_num = binding.binding;
+        let descriptor_type = descriptor_type_to_vk(&binding.descriptor_type);
+        entries.push(quote! {
+            vk::DescriptorUpdateTemplateEntry {
+                dst_binding: #binding_num,
+                dst_array_element: 0,
+                descriptor_count: #count,
+                descriptor_type: #descriptor_type,
+                offset: ::std::mem::offset_of!(#struct_name, #ident),
+                stride: #stride,
+            }
+        });
+    }
+    let num_entries = entries.len();
+    let template_type = if layout.push_descriptor {
+        let set = layout.set;
+        quote! {
+            .template_type(vk::DescriptorUpdateTemplateType::PUSH_DESCRIPTORS_KHR)
+            .set(#set)
+        }
+    } else {
+        quote!(.template_type(vk::DescriptorUpdateTemplateType::DESCRIPTOR_SET))
+    };
+    let skipped_doc = (!skipped.is_empty()).then(|| {
+        let doc = format!(
+            " Mutable and variable count bindings aren't included and need to be written with `vk::WriteDescriptorSet`: {}.",
+            skipped.join(", ")
+        );
+        quote!(#[doc = #doc])
+    });
+    quote! {
+        #skipped_doc
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        pub struct #struct_name {
+            #(#fields),*
+        }
+
+        impl Default for #struct_name {
+            fn default() -> Self {
+                Self {
+                    #(#defaults),*
+                }
+            }
+        }
+
+        impl #struct_name {
+            pub const ENTRIES: [vk::DescriptorUpdateTemplateEntry; #num_entries] = [#(#entries),*];
+
+            /// The descriptor set layout, or the pipeline bind point and layout for push
+            /// descriptors, are left to the caller.
+            pub fn create_info() -> vk::DescriptorUpdateTemplateCreateInfo<'static> {
+                vk::DescriptorUpdateTemplateCreateInfo::default()
+                    .descriptor_update_entries(&Self::ENTRIES)
+                    #template_type
+            }
+
+            pub fn as_ptr(&self) -> *const ::std::ffi::c_void {
+                self as *const Self as *const ::std::ffi::c_void
+            }
+        }
+    }
+}
+
+pub fn samplers_to_vk(module: &PlayoutModule) -> TokenStream {
+    let samplers = module.samplers.values().map(sampler_to_vk);
+    quote! {[
+        #(#samplers),*
+    ]}
+}
+
+// VkSamplerCreateInfo
+fn sampler_to_vk(sampler: &ImmutableSampler) -> TokenStream {
+    let fields = sampler.vk_fields().into_iter().map(|(method, value)| {
+        let method = syn::Ident::new(method, Span::call_site());
+        let value = match value {
+            SamplerFieldValue::Enum(ty, raw) => {
+                let ty = syn::Ident::new(ty, Span::call_site());
+                quote!(vk::#ty::from_raw(#raw))
+            }
+            SamplerFieldValue::Float(value) => quote!(#value),
+            SamplerFieldValue::Bool(value) => quote!(#value),
+        };
+        quote!(.#method(#value))
+    });
+    quote! {
+        vk::SamplerCreateInfo::default()
+            #(#fields)*
+    }
+}
+
+pub fn binding_flags_to_vk(layout: &SetLayout) -> TokenStream {
+    let flags = layout.bindings.iter().map(|binding| {
+        let flags = binding.flags.bits();
+        quote! {
+            vk::DescriptorBindingFlags::from_raw(#flags)
+        }
+    });
+    quote! {[
+        #(#flags),*
+    ]}
+}
+
+/// `[vk::MutableDescriptorTypeListEXT; N]` for the bindings of a set, empty for bindings that
+/// aren't mutable.
+pub fn mutable_descriptor_types_to_vk(layout: &SetLayout) -> TokenStream {
+    let lists = layout.bindings.iter().map(|binding| {
+        if let DescriptorType::Mutable { types } = &binding.descriptor_type {
+            let types = types.iter().map(descriptor_type_to_vk);
+            quote! {
+                vk::MutableDescriptorTypeListEXT::default().descriptor_types(&[#(#types),*])
+            }
+        } else {
+            quote!(vk::MutableDescriptorTypeListEXT::default())
+        }
+    });
+    quote! {[
+        #(#lists),*
+    ]}
+}
+
+pub fn set_layout_flags_to_vk(layout: &SetLayout) -> TokenStream {
+    let mut flags = Vec::new();
+    if layout.update_after_bind() {
+        flags.push(quote!(
+            vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL
+        ));
+    }
+    if layout.push_descriptor {
+        flags.push(quote!(
+            vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR
+        ));
+    }
+    if flags.is_empty() {
+        quote!(vk::DescriptorSetLayoutCreateFlags::empty())
+    } else {
+        quote!(#(#flags)|*)
+    }
+}
+
 // VkDescriptorSetLayoutBinding
-fn binding_to_vk(module: &PlayoutModule, binding: &Binding) -> TokenStream {
+fn binding_to_vk(
+    module: &PlayoutModule,
+    binding: &Binding,
+    immutable_samplers: TokenStream,
+) -> TokenStream {
     let binding_num = binding.binding;
     let mut count_num = binding.descriptor_count;
     let descriptor_type = descriptor_type_to_vk(&binding.descriptor_type);
     let shader_stage_flags = stage_flag_to_vk(&binding.stages);
 
     if let DescriptorType::InlineUniformBlock { ty } = &binding.descriptor_type {
-        let layout = ty.layout(module);
+        let layout = ty
+            .layout_in(module, binding.block_layout(), MatrixLayout::default())
+            .unwrap();
         assert_eq!(count_num, 1);
         count_num = layout.size() as u32;
     }
@@ -61,7 +418,7 @@ fn binding_to_vk(module: &PlayoutModule, binding: &Binding) -> TokenStream {
             descriptor_type: #descriptor_type,
             descriptor_count: #count_num,
             stage_flags: #shader_stage_flags,
-            p_immutable_samplers: ::std::ptr::null(),
+            p_immutable_samplers: #immutable_samplers,
             _marker: ::std::marker::PhantomData,
         }
     }
@@ -88,30 +445,8 @@ fn stage_flag_to_vk(stage_flag: &ShaderStages) -> TokenStream {
 }
 
 pub(crate) fn descriptor_type_to_vk(descriptor_type: &DescriptorType) -> TokenStream {
-    match descriptor_type {
-        DescriptorType::Sampler => quote! {
-            vk::DescriptorType::SAMPLER
-        },
-        DescriptorType::CombinedImageSampler => quote! {
-            vk::DescriptorType::COMBINED_IMAGE_SAMPLER
-        },
-        DescriptorType::StorageImage { .. } => quote! {
-            vk::DescriptorType::STORAGE_IMAGE
-        },
-        DescriptorType::SampledImage => quote! {
-            vk::DescriptorType::SAMPLED_IMAGE
-        },
-        DescriptorType::UniformBuffer { .. } => quote! {
-            vk::DescriptorType::UNIFORM_BUFFER
-        },
-        DescriptorType::InlineUniformBlock { .. } => quote! {
-            vk::DescriptorType::INLINE_UNIFORM_BLOCK
-        },
-        DescriptorType::StorageBuffer { .. } => quote! {
-            vk::DescriptorType::STORAGE_BUFFER
-        },
-        DescriptorType::AccelerationStructure => quote! {
-            vk::DescriptorType::ACCELERATION_STRUCTURE_KHR
-        },
+    let name = syn::Ident::new(descriptor_type.vk_descriptor_type_name(), Span::call_site());
+    quote! {
+        vk::DescriptorType::#name
     }
 }
@@ -1,6 +1,6 @@
 use std::collections::{BTreeMap, BTreeSet};
 
-use playout::{Binding, PlayoutModule};
+use playout::{Binding, BindingFlags, PlayoutModule};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
 use syn::{parse::Parse, punctuated::Punctuated, spanned::Spanned};
@@ -110,6 +110,26 @@ impl DescriptorSetWriteUpdate {
             );
             return Err((self.name.span(), message));
         };
+        // Push descriptor sets have no vk::DescriptorSet, and are written with `_` as destination.
+        let push = matches!(self.dst, syn::Expr::Infer(_));
+        if push && !set.push_descriptor {
+            return Err((
+                self.dst.span(),
+                format!(
+                    "{} is not a push descriptor set and needs a destination set",
+                    set.name
+                ),
+            ));
+        }
+        if !push && set.push_descriptor {
+            return Err((
+                self.dst.span(),
+                format!(
+                    "{} is a push descriptor set, write it with `_` as destination for cmd_push_descriptor_set",
+                    set.name
+                ),
+            ));
+        }
         let map_name_to_binding_id = set
             .bindings
             .iter()
@@ -126,6 +146,10 @@ impl DescriptorSetWriteUpdate {
             let Some(binding) = map_name_to_binding_id.get(&field.name.to_string()).cloned() else {
                 return Err((field.name.span(), "Binding not found".to_string()));
             };
+            if let playout::DescriptorType::Mutable { .. } = binding.descriptor_type {
+                let message = "Mutable descriptors need a concrete descriptor type, write them with vk::WriteDescriptorSet".to_string();
+                return Err((field.name.span(), message));
+            }
             let subscript = if let Some(subscript_lit) = &field.subscript {
                 let Ok(subscript) = subscript_lit.base10_parse::<u32>() else {
                     return Err((
@@ -146,7 +170,17 @@ impl DescriptorSetWriteUpdate {
                 }
                 subscript
             } else {
-                if field.values.len() != binding.descriptor_count as usize {
+                let variable_count = binding
+                    .flags
+                    .contains(BindingFlags::VARIABLE_DESCRIPTOR_COUNT);
+                if variable_count && field.values.len() > binding.descriptor_count as usize {
+                    let message = format!(
+                        "Expected array literal of at most {} descriptors",
+                        binding.descriptor_count
+                    );
+                    return Err((field.values.span(), message));
+                }
+                if !variable_count && field.values.len() != binding.descriptor_count as usize {
                     let message = format!(
                         "Expected array literal of length {}",
                         binding.descriptor_count
@@ -230,21 +264,33 @@ impl DescriptorSetWriteUpdate {
             .map(|(_field, binding, subscript, _, descs)| {
                 let dst = self.dst.to_token_stream();
                 let count = descs.len() as u32;
+                let dst_binding = binding.binding;
                 let descriptor_type = crate::vk::descriptor_type_to_vk(&binding.descriptor_type);
                 let ptr_quote = match &binding.descriptor_type {
-                    playout::DescriptorType::Sampler
-                    | playout::DescriptorType::CombinedImageSampler
+                    playout::DescriptorType::Sampler { .. }
+                    | playout::DescriptorType::CombinedImageSampler { .. }
                     | playout::DescriptorType::StorageImage { .. }
-                    | playout::DescriptorType::SampledImage => {
+                    | playout::DescriptorType::SampledImage { .. }
+                    | playout::DescriptorType::InputAttachment { .. } => {
                         let index = ctx.img_info.len();
                         ctx.img_info.extend(descs);
                         quote! {
                             p_image_info: unsafe{img_info.as_ptr().add(#index)}
                         }
                     }
+                    playout::DescriptorType::UniformTexelBuffer { .. }
+                    | playout::DescriptorType::StorageTexelBuffer { .. } => {
+                        let index = ctx.buffer_view.len();
+                        ctx.buffer_view.extend(descs);
+                        quote! {
+                            p_texel_buffer_view: unsafe{buffer_view.as_ptr().add(#index)}
+                        }
+                    }
                     playout::DescriptorType::UniformBuffer { ty: _ }
                     | playout::DescriptorType::InlineUniformBlock { ty: _ }
-                    | playout::DescriptorType::StorageBuffer { ty: _ } => {
+                    | playout::DescriptorType::StorageBuffer { ty: _ }
+                    | playout::DescriptorType::UniformBufferDynamic { ty: _ }
+                    | playout::DescriptorType::StorageBufferDynamic { ty: _ } => {
                         let index = ctx.buffer_info.len();
                         ctx.buffer_info.extend(descs);
                         quote! {
@@ -252,10 +298,17 @@ impl DescriptorSetWriteUpdate {
                         }
                     }
                     playout::DescriptorType::AccelerationStructure => todo!(),
+                    playout::DescriptorType::Mutable { .. } => unreachable!(),
+                };
+                let dst_set = if push {
+                    quote!()
+                } else {
+                    quote!(dst_set: #dst,)
                 };
                 quote::quote! {
                     vk::WriteDescriptorSet {
-                        dst_set: #dst,
+                        #dst_set
+                        dst_binding: #dst_binding,
                         dst_array_element: #subscript,
                         descriptor_count: #count,
                         descriptor_type: #descriptor_type,
@@ -277,31 +330,25 @@ impl DescriptorSetWriteArgs {
             };
         }
 
-        let path = self
-            .playout_path
-            .span()
-            .unwrap()
-            .source_file()
-            .path()
-            .join(self.playout_path.value());
-        let file = match std::fs::read_to_string(path) {
-            Ok(file) => file,
-            Err(err) => {
-                let message = err.to_string();
-                return quote! {
-                    compile_error!(#message)
-                };
-            }
-        };
-        let module = match PlayoutModule::try_from(file.as_str()) {
+        let module = match crate::load_module(
+            &self.playout_path.value(),
+            self.playout_path.span().unwrap(),
+        ) {
             Ok(module) => module,
-            Err(err) => {
-                let message = err.to_string();
-                return quote! {
-                    compile_error!(#message)
+            Err(err) => return err,
+        };
+
+        if self.updates.len() > 1 {
+            if let Some(update) = self
+                .updates
+                .iter()
+                .find(|update| matches!(update.dst, syn::Expr::Infer(_)))
+            {
+                return quote_spanned! { update.dst.span() =>
+                    compile_error!("Push descriptor writes can't be combined with other updates")
                 };
             }
-        };
+        }
 
         let mut token_streams = Vec::new();
         let mut ctx = DescriptorSetWriteCtx::default();
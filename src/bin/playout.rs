@@ -0,0 +1,97 @@
+//! Command line interface for inspecting playout files.
+//!
+//! ```text
+//! playout glsl <file> [--no-extensions]
+//! playout padding <file> [std140 | std430 | scalar]
+//! playout limits <file> [vulkan13 | roadmap2022]
+//! playout limits <file> <profiles.json> <profile>
+//! ```
+//!
+//! Loading limits from a Vulkan Profiles file requires the `profiles` feature.
+
+use playout::{BlockLayout, Limits, PlayoutModule};
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("    playout glsl <file> [--no-extensions]");
+    eprintln!("    playout padding <file> [std140 | std430 | scalar]");
+    eprintln!("    playout limits <file> [vulkan13 | roadmap2022]");
+    #[cfg(feature = "profiles")]
+    eprintln!("    playout limits <file> <profiles.json> <profile>");
+    std::process::exit(2);
+}
+
+fn load(path: &str) -> PlayoutModule {
+    let source = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Unable to read {}: {}", path, err);
+        std::process::exit(1);
+    });
+    PlayoutModule::try_from(source.as_str()).unwrap_or_else(|err| {
+        let start = err.span().start();
+        eprintln!("{}:{}:{}: {}", path, start.line, start.column + 1, err);
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["glsl", path, rest @ ..] if rest.is_empty() || rest == ["--no-extensions"] => {
+            let module = load(path);
+            let mut out = String::new();
+            if rest.is_empty() {
+                module.show(&mut out);
+            } else {
+                module.show_declarations(&mut out);
+            }
+            println!("{}", out);
+        }
+        ["padding", path, rest @ ..] if rest.len() <= 1 => {
+            let block = match rest.first() {
+                Some(name) => BlockLayout::from_name(name).unwrap_or_else(|| usage()),
+                None => BlockLayout::default(),
+            };
+            let module = load(path);
+            let reports = module.padding_reports(block).unwrap_or_else(|err| {
+                eprintln!("{}: {}", path, err);
+                std::process::exit(1);
+            });
+            for report in reports {
+                print!("{}", report);
+            }
+        }
+        ["limits", path, rest @ ..] => {
+            let limits = match rest {
+                [] | ["vulkan13"] => Limits::VULKAN_1_3,
+                ["roadmap2022"] => Limits::ROADMAP_2022,
+                #[cfg(feature = "profiles")]
+                [profiles, profile] => {
+                    let json = std::fs::read_to_string(profiles).unwrap_or_else(|err| {
+                        eprintln!("Unable to read {}: {}", profiles, err);
+                        std::process::exit(1);
+                    });
+                    Limits::from_profiles_json(&json, profile, Limits::VULKAN_1_3).unwrap_or_else(
+                        |err| {
+                            eprintln!("{}: {}", profiles, err);
+                            std::process::exit(1);
+                        },
+                    )
+                }
+                _ => usage(),
+            };
+            let violations = load(path).check_limits(&limits);
+            for violation in violations.iter() {
+                println!("{}", violation);
+            }
+            if !violations.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        _ => usage(),
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{DescriptorType, LayoutError, MatrixLayout, PlayoutModule, SetLayout};
+
+/// Descriptor sizes from `VkPhysicalDeviceDescriptorBufferPropertiesEXT`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DescriptorSizes {
+    pub sampler: usize,
+    pub combined_image_sampler: usize,
+    pub sampled_image: usize,
+    pub storage_image: usize,
+    pub uniform_texel_buffer: usize,
+    pub storage_texel_buffer: usize,
+    pub uniform_buffer: usize,
+    pub storage_buffer: usize,
+    pub input_attachment: usize,
+    pub acceleration_structure: usize,
+}
+
+impl DescriptorSizes {
+    /// Size of one descriptor, or `None` for types not stored as descriptors in descriptor buffers.
+    pub fn size_of(&self, descriptor_type: &DescriptorType) -> Option<usize> {
+        match descriptor_type {
+            DescriptorType::Sampler { .. } => Some(self.sampler),
+            DescriptorType::CombinedImageSampler { .. } => Some(self.combined_image_sampler),
+            DescriptorType::SampledImage { .. } => Some(self.sampled_image),
+            DescriptorType::StorageImage { .. } => Some(self.storage_image),
+            DescriptorType::UniformTexelBuffer { .. } => Some(self.uniform_texel_buffer),
+            DescriptorType::StorageTexelBuffer { .. } => Some(self.storage_texel_buffer),
+            DescriptorType::UniformBuffer { .. } => Some(self.uniform_buffer),
+            DescriptorType::StorageBuffer { .. } => Some(self.storage_buffer),
+            DescriptorType::InputAttachment { .. } => Some(self.input_attachment),
+            DescriptorType::AccelerationStructure => Some(self.acceleration_structure),
+            // Large enough for any of the member types.
+            DescriptorType::Mutable { types } => {
+                types.iter().filter_map(|ty| self.size_of(ty)).max()
+            }
+            DescriptorType::UniformBufferDynamic { .. }
+            | DescriptorType::StorageBufferDynamic { .. }
+            | DescriptorType::InlineUniformBlock { .. } => None,
+        }
+    }
+}
+
+/// Where the descriptors of a binding are stored in a descriptor buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptorBufferBinding {
+    pub binding: u32,
+    pub offset: usize,
+    /// Distance between array elements. 1 for inline uniform blocks, whose count is in bytes.
+    pub descriptor_size: usize,
+    pub descriptor_count: u32,
+}
+
+/// Placement of a set in a descriptor buffer, as returned by `vkGetDescriptorSetLayoutSizeEXT`
+/// and `vkGetDescriptorSetLayoutBindingOffsetEXT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorBufferLayout {
+    /// Ordered by binding number.
+    pub bindings: Vec<DescriptorBufferBinding>,
+    pub size: usize,
+}
+
+impl DescriptorBufferLayout {
+    pub fn binding(&self, binding: u32) -> Option<&DescriptorBufferBinding> {
+        self.bindings.iter().find(|entry| entry.binding == binding)
+    }
+
+    /// Byte offset of descriptor `element` of `binding`.
+    pub fn descriptor_offset(&self, binding: u32, element: u32) -> Option<usize> {
+        let entry = self.binding(binding)?;
+        if element >= entry.descriptor_count {
+            return None;
+        }
+        Some(entry.offset + element as usize * entry.descriptor_size)
+    }
+}
+
+impl SetLayout {
+    /// Packs bindings in binding number order, each aligned to its descriptor size, which is
+    /// how drivers commonly lay out sets. Drivers are free to differ, so the offsets should be
+    /// checked against `vkGetDescriptorSetLayoutBindingOffsetEXT` on new devices.
+    pub fn descriptor_buffer_layout(
+        &self,
+        module: &PlayoutModule,
+        sizes: &DescriptorSizes,
+    ) -> Result<DescriptorBufferLayout, LayoutError> {
+        let mut bindings: Vec<_> = self.bindings.iter().collect();
+        bindings.sort_by_key(|binding| binding.binding);
+
+        let mut entries = Vec::with_capacity(bindings.len());
+        let mut end: usize = 0;
+        for binding in bindings {
+            let (descriptor_size, descriptor_count, align) = match &binding.descriptor_type {
+                DescriptorType::InlineUniformBlock { ty } => {
+                    let layout =
+                        ty.layout_in(module, binding.block_layout(), MatrixLayout::default())?;
+                    (1, layout.size() as u32, layout.align())
+                }
+                DescriptorType::UniformBufferDynamic { .. }
+                | DescriptorType::StorageBufferDynamic { .. } => {
+                    return Err(LayoutError {
+                        message: format!(
+                            "Dynamic buffer `{}` can't be used with descriptor buffers",
+                            binding.ident
+                        ),
+                    })
+                }
+                descriptor_type => {
+                    let size = sizes.size_of(descriptor_type).unwrap();
+                    (size, binding.descriptor_count, size.max(1))
+                }
+            };
+            let offset = end.next_multiple_of(align);
+            end = offset + descriptor_size * descriptor_count as usize;
+            entries.push(DescriptorBufferBinding {
+                binding: binding.binding,
+                offset,
+                descriptor_size,
+                descriptor_count,
+            });
+        }
+        Ok(DescriptorBufferLayout {
+            bindings: entries,
+            size: end,
+        })
+    }
+}
+
+/// Places descriptors returned by `vkGetDescriptorEXT` into the memory of a set in a
+/// descriptor buffer.
+pub struct DescriptorBufferWriter<'a> {
+    pub(crate) set: &'a SetLayout,
+    pub(crate) layout: &'a DescriptorBufferLayout,
+    pub(crate) data: &'a mut [u8],
+}
+
+impl<'a> DescriptorBufferWriter<'a> {
+    /// `data` is the memory of the set, starting at its offset in the descriptor buffer.
+    pub fn new(set: &'a SetLayout, layout: &'a DescriptorBufferLayout, data: &'a mut [u8]) -> Self {
+        assert!(
+            data.len() >= layout.size,
+            "Set {} needs {} bytes of descriptor buffer, got {}",
+            set.name,
+            layout.size,
+            data.len()
+        );
+        Self { set, layout, data }
+    }
+
+    /// The bytes of descriptor `element` of the binding named `name`.
+    pub fn descriptor_mut(&mut self, name: &str, element: u32) -> Option<&mut [u8]> {
+        let binding = self
+            .set
+            .bindings
+            .iter()
+            .find(|binding| binding.ident == name)?;
+        let entry = self.layout.binding(binding.binding)?;
+        let offset = self.layout.descriptor_offset(binding.binding, element)?;
+        Some(&mut self.data[offset..offset + entry.descriptor_size])
+    }
+
+    /// Copy a descriptor into the binding named `name`.
+    pub fn write_bytes(&mut self, name: &str, element: u32, descriptor: &[u8]) {
+        let Some(dst) = self.descriptor_mut(name, element) else {
+            panic!(
+                "No descriptor {}[{}] in set {}",
+                name, element, self.set.name
+            );
+        };
+        assert_eq!(
+            dst.len(),
+            descriptor.len(),
+            "Descriptor size mismatch for {}",
+            name
+        );
+        dst.copy_from_slice(descriptor);
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod tests {
+    use super::*;
+
+    const SIZES: DescriptorSizes = DescriptorSizes {
+        sampler: 16,
+        combined_image_sampler: 48,
+        sampled_image: 32,
+        storage_image: 32,
+        uniform_texel_buffer: 16,
+        storage_texel_buffer: 16,
+        uniform_buffer: 16,
+        storage_buffer: 16,
+        input_attachment: 32,
+        acceleration_structure: 8,
+    };
+
+    fn layout(bindings: &str) -> Result<DescriptorBufferLayout, LayoutError> {
+        let source = format!(
+            "struct S {{ a: f32, b: Vec4 }}
+            #[set] struct Set {{ #![stage(COMPUTE)] {bindings} }}"
+        );
+        let module = PlayoutModule::try_from(source.as_str()).unwrap();
+        module.descriptor_sets[0].descriptor_buffer_layout(&module, &SIZES)
+    }
+
+    fn offsets(layout: &DescriptorBufferLayout) -> Vec<(usize, usize, u32)> {
+        layout
+            .bindings
+            .iter()
+            .map(|entry| (entry.offset, entry.descriptor_size, entry.descriptor_count))
+            .collect()
+    }
+
+    #[test]
+    fn mixed_types() {
+        let layout = layout(
+            "a: UniformBuffer<S>, b: [SampledImage; 3], c: AccelerationStructure, d: StorageBuffer<[u32]>",
+        )
+        .unwrap();
+        assert_eq!(
+            offsets(&layout),
+            [(0, 16, 1), (32, 32, 3), (128, 8, 1), (144, 16, 1)]
+        );
+        assert_eq!(layout.size, 160);
+        assert_eq!(layout.descriptor_offset(1, 2), Some(96));
+        assert_eq!(layout.descriptor_offset(1, 3), None);
+    }
+
+    #[test]
+    fn alignment_padding() {
+        // The sampled image is aligned to its 32 byte descriptor size.
+        let layout = layout("a: AccelerationStructure, b: SampledImage").unwrap();
+        assert_eq!(offsets(&layout), [(0, 8, 1), (32, 32, 1)]);
+        assert_eq!(layout.size, 64);
+    }
+
+    #[test]
+    fn inline_uniform_block() {
+        // Counted in bytes and aligned to the block's alignment.
+        let layout = layout("a: AccelerationStructure, b: InlineUniformBlock<S>").unwrap();
+        assert_eq!(offsets(&layout), [(0, 8, 1), (16, 1, 32)]);
+        assert_eq!(layout.size, 48);
+    }
+
+    #[test]
+    fn mutable() {
+        // Large enough for the largest member type.
+        let layout = layout(
+            "a: AccelerationStructure, b: [Mutable<SampledImage | StorageBuffer<[u32]>>; 2]",
+        )
+        .unwrap();
+        assert_eq!(offsets(&layout), [(0, 8, 1), (32, 32, 2)]);
+        assert_eq!(layout.size, 96);
+    }
+
+    #[test]
+    fn dynamic_buffer() {
+        for binding in [
+            "buf: UniformBufferDynamic<S>",
+            "buf: StorageBufferDynamic<[u32]>",
+        ] {
+            let err = layout(binding).unwrap_err();
+            assert_eq!(
+                err.message,
+                "Dynamic buffer `buf` can't be used with descriptor buffers"
+            );
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+
+/// A `VkDeviceAddress` of a `T`, or of the first element when `T` is a slice. This is how
+/// `Ptr<T>` fields are represented in the structs generated by `types!`.
+#[repr(transparent)]
+pub struct DevicePtr<T: ?Sized> {
+    pub address: u64,
+    _marker: PhantomData<fn() -> *const T>,
+}
+
+impl<T: ?Sized> DevicePtr<T> {
+    pub const fn new(address: u64) -> Self {
+        Self {
+            address,
+            _marker: PhantomData,
+        }
+    }
+    pub const fn null() -> Self {
+        Self::new(0)
+    }
+    pub const fn is_null(self) -> bool {
+        self.address == 0
+    }
+}
+
+impl<T: ?Sized> Clone for DevicePtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for DevicePtr<T> {}
+
+impl<T: ?Sized> Default for DevicePtr<T> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T: ?Sized> PartialEq for DevicePtr<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl<T: ?Sized> Eq for DevicePtr<T> {}
+
+impl<T: ?Sized> std::hash::Hash for DevicePtr<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for DevicePtr<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DevicePtr({:#x})", self.address)
+    }
+}
+
+impl<T: ?Sized> From<DevicePtr<T>> for u64 {
+    fn from(ptr: DevicePtr<T>) -> Self {
+        ptr.address
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    Binding, BindingFlags, BlockLayout, DescriptorAccess, DescriptorType, ImageDimension,
+    PlayoutModule, PrimitiveType, PrimitiveTypeSingle, SamplerAddressMode, ShaderStages, Type,
+};
+
+macro_rules! device_features {
+    ($($flag:ident = $bit:literal => $name:literal,)*) => {
+        bitflags::bitflags! {
+            /// Optional Vulkan device features required by a module.
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct DeviceFeatures: u64 {
+                $(const $flag = 1 << $bit;)*
+            }
+        }
+
+        impl DeviceFeatures {
+            /// Names of the features as spelled in the Vulkan feature structs, such as
+            /// `storageBuffer16BitAccess`.
+            pub fn vk_names(self) -> impl Iterator<Item = &'static str> {
+                [$((DeviceFeatures::$flag, $name)),*]
+                    .into_iter()
+                    .filter(move |(flag, _)| self.contains(*flag))
+                    .map(|(_, name)| name)
+            }
+        }
+    };
+}
+
+device_features! {
+    SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT = 0 => "shaderStorageImageReadWithoutFormat",
+    SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT = 1 => "shaderStorageImageWriteWithoutFormat",
+    SHADER_STORAGE_IMAGE_MULTISAMPLE = 2 => "shaderStorageImageMultisample",
+    IMAGE_CUBE_ARRAY = 3 => "imageCubeArray",
+    GEOMETRY_SHADER = 4 => "geometryShader",
+    TESSELLATION_SHADER = 5 => "tessellationShader",
+    SAMPLER_ANISOTROPY = 6 => "samplerAnisotropy",
+    SHADER_FLOAT64 = 7 => "shaderFloat64",
+    SHADER_INT64 = 8 => "shaderInt64",
+    SHADER_INT16 = 9 => "shaderInt16",
+    STORAGE_BUFFER_16BIT_ACCESS = 10 => "storageBuffer16BitAccess",
+    UNIFORM_AND_STORAGE_BUFFER_16BIT_ACCESS = 11 => "uniformAndStorageBuffer16BitAccess",
+    STORAGE_PUSH_CONSTANT_16 = 12 => "storagePushConstant16",
+    SAMPLER_MIRROR_CLAMP_TO_EDGE = 13 => "samplerMirrorClampToEdge",
+    STORAGE_BUFFER_8BIT_ACCESS = 14 => "storageBuffer8BitAccess",
+    UNIFORM_AND_STORAGE_BUFFER_8BIT_ACCESS = 15 => "uniformAndStorageBuffer8BitAccess",
+    STORAGE_PUSH_CONSTANT_8 = 16 => "storagePushConstant8",
+    SHADER_FLOAT16 = 17 => "shaderFloat16",
+    SHADER_INT8 = 18 => "shaderInt8",
+    RUNTIME_DESCRIPTOR_ARRAY = 19 => "runtimeDescriptorArray",
+    DESCRIPTOR_BINDING_PARTIALLY_BOUND = 20 => "descriptorBindingPartiallyBound",
+    DESCRIPTOR_BINDING_VARIABLE_DESCRIPTOR_COUNT = 21 => "descriptorBindingVariableDescriptorCount",
+    DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING = 22 => "descriptorBindingUpdateUnusedWhilePending",
+    DESCRIPTOR_BINDING_UNIFORM_BUFFER_UPDATE_AFTER_BIND = 23 => "descriptorBindingUniformBufferUpdateAfterBind",
+    DESCRIPTOR_BINDING_SAMPLED_IMAGE_UPDATE_AFTER_BIND = 24 => "descriptorBindingSampledImageUpdateAfterBind",
+    DESCRIPTOR_BINDING_STORAGE_IMAGE_UPDATE_AFTER_BIND = 25 => "descriptorBindingStorageImageUpdateAfterBind",
+    DESCRIPTOR_BINDING_STORAGE_BUFFER_UPDATE_AFTER_BIND = 26 => "descriptorBindingStorageBufferUpdateAfterBind",
+    DESCRIPTOR_BINDING_UNIFORM_TEXEL_BUFFER_UPDATE_AFTER_BIND = 27 => "descriptorBindingUniformTexelBufferUpdateAfterBind",
+    DESCRIPTOR_BINDING_STORAGE_TEXEL_BUFFER_UPDATE_AFTER_BIND = 28 => "descriptorBindingStorageTexelBufferUpdateAfterBind",
+    SCALAR_BLOCK_LAYOUT = 29 => "scalarBlockLayout",
+    BUFFER_DEVICE_ADDRESS = 30 => "bufferDeviceAddress",
+    INLINE_UNIFORM_BLOCK = 31 => "inlineUniformBlock",
+    DESCRIPTOR_BINDING_INLINE_UNIFORM_BLOCK_UPDATE_AFTER_BIND = 32 => "descriptorBindingInlineUniformBlockUpdateAfterBind",
+    ACCELERATION_STRUCTURE = 33 => "accelerationStructure",
+    DESCRIPTOR_BINDING_ACCELERATION_STRUCTURE_UPDATE_AFTER_BIND = 34 => "descriptorBindingAccelerationStructureUpdateAfterBind",
+    RAY_TRACING_PIPELINE = 35 => "rayTracingPipeline",
+    RAY_QUERY = 36 => "rayQuery",
+    MUTABLE_DESCRIPTOR_TYPE = 37 => "mutableDescriptorType",
+    TASK_SHADER = 38 => "taskShader",
+    MESH_SHADER = 39 => "meshShader",
+    SHADER_IMAGE_INT64_ATOMICS = 40 => "shaderImageInt64Atomics",
+    FRAGMENT_STORES_AND_ATOMICS = 41 => "fragmentStoresAndAtomics",
+    VERTEX_PIPELINE_STORES_AND_ATOMICS = 42 => "vertexPipelineStoresAndAtomics",
+}
+
+const RAY_TRACING_STAGES: ShaderStages = ShaderStages::RAYGEN
+    .union(ShaderStages::ANY_HIT)
+    .union(ShaderStages::CLOSEST_HIT)
+    .union(ShaderStages::MISS)
+    .union(ShaderStages::INTERSECTION)
+    .union(ShaderStages::CALLABLE);
+
+impl ShaderStages {
+    pub fn required_features(&self) -> DeviceFeatures {
+        let mut features = DeviceFeatures::empty();
+        if self.contains(ShaderStages::GEOMETRY) {
+            features |= DeviceFeatures::GEOMETRY_SHADER;
+        }
+        if self
+            .intersects(ShaderStages::TELLESLATION_CONTROL | ShaderStages::TELLESLATION_EVALUATION)
+        {
+            features |= DeviceFeatures::TESSELLATION_SHADER;
+        }
+        if self.contains(ShaderStages::TASK) {
+            features |= DeviceFeatures::TASK_SHADER;
+        }
+        if self.contains(ShaderStages::MESH) {
+            features |= DeviceFeatures::MESH_SHADER;
+        }
+        if self.intersects(RAY_TRACING_STAGES) {
+            features |= DeviceFeatures::RAY_TRACING_PIPELINE;
+        }
+        features
+    }
+}
+
+impl DescriptorType {
+    /// Features needed to use the descriptor type, and to update it after binding when
+    /// `update_after_bind` is set.
+    pub fn required_features(&self, update_after_bind: bool) -> DeviceFeatures {
+        let mut features = DeviceFeatures::empty();
+        let dimension = match self {
+            DescriptorType::StorageImage { dimension, .. } => {
+                if matches!(
+                    dimension,
+                    ImageDimension::Dim2MS | ImageDimension::Dim2MSArray
+                ) {
+                    features |= DeviceFeatures::SHADER_STORAGE_IMAGE_MULTISAMPLE;
+                }
+                Some(dimension)
+            }
+            DescriptorType::SampledImage { dimension, .. }
+            | DescriptorType::CombinedImageSampler { dimension, .. } => Some(dimension),
+            _ => None,
+        };
+        if dimension == Some(&ImageDimension::CubeArray) {
+            features |= DeviceFeatures::IMAGE_CUBE_ARRAY;
+        }
+        if let DescriptorType::StorageImage {
+            format: Some(format),
+            ..
+        }
+        | DescriptorType::StorageTexelBuffer { format } = self
+        {
+            if format.is_64_bit() {
+                features |=
+                    DeviceFeatures::SHADER_IMAGE_INT64_ATOMICS | DeviceFeatures::SHADER_INT64;
+            }
+        }
+        match self {
+            DescriptorType::InlineUniformBlock { .. } => {
+                features |= DeviceFeatures::INLINE_UNIFORM_BLOCK
+            }
+            DescriptorType::AccelerationStructure => {
+                features |= DeviceFeatures::ACCELERATION_STRUCTURE
+            }
+            DescriptorType::Mutable { types } => {
+                features |= types.iter().fold(
+                    DeviceFeatures::MUTABLE_DESCRIPTOR_TYPE,
+                    |features, member| features | member.required_features(update_after_bind),
+                )
+            }
+            _ => (),
+        }
+        if update_after_bind {
+            features |= match self {
+                DescriptorType::UniformBuffer { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_UNIFORM_BUFFER_UPDATE_AFTER_BIND
+                }
+                DescriptorType::Sampler { .. }
+                | DescriptorType::SampledImage { .. }
+                | DescriptorType::CombinedImageSampler { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_SAMPLED_IMAGE_UPDATE_AFTER_BIND
+                }
+                DescriptorType::StorageImage { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_STORAGE_IMAGE_UPDATE_AFTER_BIND
+                }
+                DescriptorType::StorageBuffer { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_STORAGE_BUFFER_UPDATE_AFTER_BIND
+                }
+                DescriptorType::UniformTexelBuffer { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_UNIFORM_TEXEL_BUFFER_UPDATE_AFTER_BIND
+                }
+                DescriptorType::StorageTexelBuffer { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_STORAGE_TEXEL_BUFFER_UPDATE_AFTER_BIND
+                }
+                DescriptorType::InlineUniformBlock { .. } => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_INLINE_UNIFORM_BLOCK_UPDATE_AFTER_BIND
+                }
+                DescriptorType::AccelerationStructure => {
+                    DeviceFeatures::DESCRIPTOR_BINDING_ACCELERATION_STRUCTURE_UPDATE_AFTER_BIND
+                }
+                // Dynamic buffers and input attachments can't be updated after bind. Mutable
+                // descriptors are covered by their members.
+                DescriptorType::Mutable { .. }
+                | DescriptorType::UniformBufferDynamic { .. }
+                | DescriptorType::StorageBufferDynamic { .. }
+                | DescriptorType::InputAttachment { .. } => DeviceFeatures::empty(),
+            };
+        }
+        features
+    }
+}
+
+impl Binding {
+    /// Features needed by the descriptors of the binding. The data types inside buffers are
+    /// covered by `PlayoutModule::required_features`.
+    pub fn required_features(&self) -> DeviceFeatures {
+        let mut features = self.stages.required_features();
+        let update_after_bind = self.flags.contains(BindingFlags::UPDATE_AFTER_BIND);
+        for descriptor_type in std::iter::once(&self.descriptor_type)
+            .chain(self.aliases.iter().map(|alias| &alias.descriptor_type))
+        {
+            features |= descriptor_type.required_features(update_after_bind);
+        }
+        for member in self.declared_types() {
+            if member.block_type().is_some() && self.block_layout_of(member) == BlockLayout::Scalar
+            {
+                features |= DeviceFeatures::SCALAR_BLOCK_LAYOUT;
+            }
+        }
+        let formatless = self
+            .declared_types()
+            .any(|member| matches!(member, DescriptorType::StorageImage { format: None, .. }));
+        if formatless {
+            let access = self.access();
+            if access.contains(DescriptorAccess::READ) {
+                features |= DeviceFeatures::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT;
+            }
+            if access.contains(DescriptorAccess::WRITE) {
+                features |= DeviceFeatures::SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT;
+            }
+        }
+        // Stores and atomics are only guaranteed in compute and ray tracing stages.
+        if self.access().contains(DescriptorAccess::WRITE) {
+            if self.stages.contains(ShaderStages::FRAGMENT) {
+                features |= DeviceFeatures::FRAGMENT_STORES_AND_ATOMICS;
+            }
+            if self.stages.intersects(
+                ShaderStages::VERTEX
+                    | ShaderStages::TELLESLATION_CONTROL
+                    | ShaderStages::TELLESLATION_EVALUATION
+                    | ShaderStages::GEOMETRY,
+            ) {
+                features |= DeviceFeatures::VERTEX_PIPELINE_STORES_AND_ATOMICS;
+            }
+        }
+        if self.flags.contains(BindingFlags::PARTIALLY_BOUND) {
+            features |= DeviceFeatures::DESCRIPTOR_BINDING_PARTIALLY_BOUND;
+        }
+        if self
+            .flags
+            .contains(BindingFlags::UPDATE_UNUSED_WHILE_PENDING)
+        {
+            features |= DeviceFeatures::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING;
+        }
+        // Variable count bindings are declared as unsized arrays in shaders.
+        if self.flags.contains(BindingFlags::VARIABLE_DESCRIPTOR_COUNT) {
+            features |= DeviceFeatures::DESCRIPTOR_BINDING_VARIABLE_DESCRIPTOR_COUNT
+                | DeviceFeatures::RUNTIME_DESCRIPTOR_ARRAY;
+        }
+        // Shaders outside of ray tracing pipelines trace rays with ray queries.
+        let acceleration_structure = self
+            .declared_types()
+            .any(|member| matches!(member, DescriptorType::AccelerationStructure));
+        if acceleration_structure && !self.stages.difference(RAY_TRACING_STAGES).is_empty() {
+            features |= DeviceFeatures::RAY_QUERY;
+        }
+        features
+    }
+}
+
+/// Where data of some type is stored, which decides the 8 and 16-bit access features it needs.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StorageClass {
+    StorageBuffer,
+    UniformBuffer,
+    PushConstant,
+}
+
+impl PlayoutModule {
+    pub fn required_features(&self) -> DeviceFeatures {
+        let mut features = self
+            .descriptor_sets
+            .iter()
+            .flat_map(|set| set.bindings.iter())
+            .fold(DeviceFeatures::empty(), |features, binding| {
+                features | binding.required_features()
+            });
+        for field in self.push_constants.fields.iter() {
+            features |= field.stages.required_features();
+        }
+        for sampler in self.samplers.values() {
+            if sampler.max_anisotropy.is_some() {
+                features |= DeviceFeatures::SAMPLER_ANISOTROPY;
+            }
+            if [
+                sampler.address_mode_u,
+                sampler.address_mode_v,
+                sampler.address_mode_w,
+            ]
+            .contains(&SamplerAddressMode::MirrorClampToEdge)
+            {
+                features |= DeviceFeatures::SAMPLER_MIRROR_CLAMP_TO_EDGE;
+            }
+        }
+
+        // Scalar types by where they are stored. Pointees live in physical storage buffers.
+        let mut stored: Vec<(StorageClass, PrimitiveTypeSingle)> = Vec::new();
+        let mut pointees: Vec<&Type> = Vec::new();
+        for binding in self
+            .descriptor_sets
+            .iter()
+            .flat_map(|set| set.bindings.iter())
+        {
+            for member in binding.declared_types() {
+                let class = match member {
+                    DescriptorType::StorageBuffer { .. }
+                    | DescriptorType::StorageBufferDynamic { .. } => StorageClass::StorageBuffer,
+                    _ => StorageClass::UniformBuffer,
+                };
+                if let Some(ty) = member.block_type() {
+                    self.stored_scalars(ty, class, &mut stored, &mut pointees);
+                }
+            }
+        }
+        for field in self.push_constants.fields.iter() {
+            self.stored_scalars(
+                &field.field.ty,
+                StorageClass::PushConstant,
+                &mut stored,
+                &mut pointees,
+            );
+        }
+        let mut pointees_seen: BTreeSet<&str> = BTreeSet::new();
+        while let Some(pointee) = pointees.pop() {
+            features |= DeviceFeatures::BUFFER_DEVICE_ADDRESS;
+            if let Type::Path(path) = pointee {
+                if !pointees_seen.insert(path) {
+                    continue;
+                }
+            }
+            self.stored_scalars(
+                pointee,
+                StorageClass::StorageBuffer,
+                &mut stored,
+                &mut pointees,
+            );
+        }
+
+        for (class, ty) in stored {
+            use PrimitiveTypeSingle::*;
+            let (arithmetic, access) = match (ty, class) {
+                (F64, _) => (DeviceFeatures::SHADER_FLOAT64, DeviceFeatures::empty()),
+                (U64 | I64, _) => (DeviceFeatures::SHADER_INT64, DeviceFeatures::empty()),
+                (U8 | I8, StorageClass::StorageBuffer) => (
+                    DeviceFeatures::SHADER_INT8,
+                    DeviceFeatures::STORAGE_BUFFER_8BIT_ACCESS,
+                ),
+                (U8 | I8, StorageClass::UniformBuffer) => (
+                    DeviceFeatures::SHADER_INT8,
+                    DeviceFeatures::UNIFORM_AND_STORAGE_BUFFER_8BIT_ACCESS,
+                ),
+                (U8 | I8, StorageClass::PushConstant) => (
+                    DeviceFeatures::SHADER_INT8,
+                    DeviceFeatures::STORAGE_PUSH_CONSTANT_8,
+                ),
+                (U16 | I16 | F16, class) => (
+                    if ty == F16 {
+                        DeviceFeatures::SHADER_FLOAT16
+                    } else {
+                        DeviceFeatures::SHADER_INT16
+                    },
+                    match class {
+                        StorageClass::StorageBuffer => DeviceFeatures::STORAGE_BUFFER_16BIT_ACCESS,
+                        StorageClass::UniformBuffer => {
+                            DeviceFeatures::UNIFORM_AND_STORAGE_BUFFER_16BIT_ACCESS
+                        }
+                        StorageClass::PushConstant => DeviceFeatures::STORAGE_PUSH_CONSTANT_16,
+                    },
+                ),
+                (U32 | I32 | F32 | Bool, _) => (DeviceFeatures::empty(), DeviceFeatures::empty()),
+            };
+            features |= arithmetic | access;
+        }
+        features
+    }
+
+    /// Vulkan device extensions required by the module, other than those promoted to
+    /// Vulkan 1.3.
+    pub fn required_extensions(&self) -> Vec<&'static str> {
+        let features = self.required_features();
+        let mut extensions = Vec::new();
+        if self.descriptor_sets.iter().any(|set| set.push_descriptor) {
+            extensions.push("VK_KHR_push_descriptor");
+        }
+        if features.contains(DeviceFeatures::MUTABLE_DESCRIPTOR_TYPE) {
+            extensions.push("VK_EXT_mutable_descriptor_type");
+        }
+        if features.contains(DeviceFeatures::ACCELERATION_STRUCTURE) {
+            extensions.push("VK_KHR_acceleration_structure");
+            extensions.push("VK_KHR_deferred_host_operations");
+        }
+        if features.contains(DeviceFeatures::RAY_TRACING_PIPELINE) {
+            extensions.push("VK_KHR_ray_tracing_pipeline");
+        }
+        if features.contains(DeviceFeatures::RAY_QUERY) {
+            extensions.push("VK_KHR_ray_query");
+        }
+        if features.intersects(DeviceFeatures::TASK_SHADER | DeviceFeatures::MESH_SHADER) {
+            extensions.push("VK_EXT_mesh_shader");
+        }
+        if features.contains(DeviceFeatures::SHADER_IMAGE_INT64_ATOMICS) {
+            extensions.push("VK_EXT_shader_image_atomic_int64");
+        }
+        extensions
+    }
+
+    /// Collects the scalar types stored in a value of type `ty`, and the types it points to.
+    fn stored_scalars<'a>(
+        &'a self,
+        ty: &'a Type,
+        class: StorageClass,
+        stored: &mut Vec<(StorageClass, PrimitiveTypeSingle)>,
+        pointees: &mut Vec<&'a Type>,
+    ) {
+        let scalar = match ty {
+            Type::Primitive(
+                PrimitiveType::Single(ty)
+                | PrimitiveType::Vec { ty, .. }
+                | PrimitiveType::Mat { ty, .. },
+            )
+            | Type::Bitfield { ty, .. } => *ty,
+            Type::Array { ty, .. } | Type::Slice { ty } => {
+                return self.stored_scalars(ty, class, stored, pointees)
+            }
+            Type::Ptr { .. } => {
+                pointees.extend(ty.pointee());
+                return;
+            }
+            Type::Path(path) => {
+                if let Some(alias) = self.type_aliases.get(path) {
+                    return self.stored_scalars(&alias.ty, class, stored, pointees);
+                }
+                if let Some(data_struct) = self.data_structs.get(path) {
+                    for field in data_struct.fields.iter() {
+                        self.stored_scalars(&field.ty, class, stored, pointees);
+                    }
+                    return;
+                }
+                match self.data_enums.get(path) {
+                    Some(data_enum) => data_enum.ty,
+                    None => return,
+                }
+            }
+        };
+        if !stored.contains(&(class, scalar)) {
+            stored.push((class, scalar));
+        }
+    }
+}
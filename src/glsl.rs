@@ -7,52 +7,95 @@ use inflector::Inflector;
 
 impl crate::ImageFormat {
     pub fn to_layout_qualifier(&self) -> &'static str {
+        self.glsl_format_name()
+            .expect("Format can't be used for storage images")
+    }
+}
+
+impl crate::ImageComponentType {
+    /// Prefix of image and sampler types with this component type, as in `uimage2D`.
+    pub fn to_type_prefix(&self) -> &'static str {
+        match self {
+            crate::ImageComponentType::Float => "",
+            crate::ImageComponentType::SInt => "i",
+            crate::ImageComponentType::UInt => "u",
+        }
+    }
+}
+
+impl crate::ImageFormat {
+    /// Prefix of image types with this format, as in `uimage2D` or `u64image2D`.
+    pub fn to_type_prefix(&self) -> &'static str {
+        match self {
+            crate::ImageFormat::R64_SInt => "i64",
+            crate::ImageFormat::R64_UInt => "u64",
+            format => format.data_mode().component_type().to_type_prefix(),
+        }
+    }
+}
+
+impl crate::ImageDimension {
+    /// Suffix of image and sampler types with this dimension, as in `image2DArray`.
+    pub fn to_type_suffix(&self) -> &'static str {
         match self {
-            crate::ImageFormat::RGBA32_Float => "rgba32f",
-            crate::ImageFormat::RGBA16_Float => "rgba16f",
-            crate::ImageFormat::RG32_Float => "rg32f",
-            crate::ImageFormat::RG16_Float => "rg16f",
-            crate::ImageFormat::R11G11B10_Float => "r11f_g11f_b10f",
-            crate::ImageFormat::R32_Float => "r32f",
-            crate::ImageFormat::R16_Float => "r16f",
-            crate::ImageFormat::RGBA16_UNorm => "rgba16",
-            crate::ImageFormat::RGB10A2_UNorm => "rgb10_a2",
-            crate::ImageFormat::RGBA8_UNorm => "rgba8",
-            crate::ImageFormat::RG16_UNorm => "rg16",
-            crate::ImageFormat::RG8_UNorm => "rg8",
-            crate::ImageFormat::R16_UNorm => "r16",
-            crate::ImageFormat::R8_UNorm => "r8",
-            crate::ImageFormat::RGBA16_SNorm => "rgba16_snorm",
-            crate::ImageFormat::RBGA8_SNorm => "rgba8_snorm",
-            crate::ImageFormat::RG16_SNorm => "rg16_snorm",
-            crate::ImageFormat::RG8_SNorm => "rg8_snorm",
-            crate::ImageFormat::R16_SNorm => "r16_snorm",
-            crate::ImageFormat::R8_SNorm => "r8_snorm",
-            crate::ImageFormat::RGBA32_SInt => "rgba32i",
-            crate::ImageFormat::RGBA16_SInt => "rgba16i",
-            crate::ImageFormat::RGBA8_SInt => "rgba8i",
-            crate::ImageFormat::RG32_SInt => "rg32i",
-            crate::ImageFormat::RG16_SInt => "rg16i",
-            crate::ImageFormat::RG8_SInt => "rg8i",
-            crate::ImageFormat::R32_SInt => "r32i",
-            crate::ImageFormat::R16_SInt => "r16i",
-            crate::ImageFormat::R8_SInt => "r8i",
-            crate::ImageFormat::RGBA32_UInt => "rgba32ui",
-            crate::ImageFormat::RGBA16_UInt => "rgba16ui",
-            crate::ImageFormat::RGB10A2_UInt => "rgb10a2ui",
-            crate::ImageFormat::RGBA8_UInt => "rgba8ui",
-            crate::ImageFormat::RG32_UInt => "rg32ui",
-            crate::ImageFormat::RG16_UInt => "rg16ui",
-            crate::ImageFormat::RG8_UInt => "rg8ui",
-            crate::ImageFormat::R32_UInt => "r32ui",
-            crate::ImageFormat::R16_UInt => "r16ui",
-            crate::ImageFormat::R8_UInt => "r8ui",
+            crate::ImageDimension::Dim1 => "1D",
+            crate::ImageDimension::Dim2 => "2D",
+            crate::ImageDimension::Dim3 => "3D",
+            crate::ImageDimension::Cube => "Cube",
+            crate::ImageDimension::Dim1Array => "1DArray",
+            crate::ImageDimension::Dim2Array => "2DArray",
+            crate::ImageDimension::CubeArray => "CubeArray",
+            crate::ImageDimension::Dim2MS => "2DMS",
+            crate::ImageDimension::Dim2MSArray => "2DMSArray",
         }
     }
 }
 
 impl crate::Binding {
+    /// The declaration of a binding. Mutable bindings and aliases are declared more than once,
+    /// and this only returns the first declaration.
+    #[deprecated(note = "use `to_declarations`, which includes mutable members and aliases")]
     pub fn to_declaration(&self, module: &PlayoutModule, set_id: u32) -> glsl::syntax::Declaration {
+        self.to_declarations(module, set_id).swap_remove(0)
+    }
+
+    /// Members of mutable bindings alias the same set and binding, named after their type as in
+    /// `heap_sampled_image`, followed by the aliases declared with `#[alias(...)]`.
+    pub fn to_declarations(
+        &self,
+        module: &PlayoutModule,
+        set_id: u32,
+    ) -> Vec<glsl::syntax::Declaration> {
+        let mut declarations = match &self.descriptor_type {
+            crate::DescriptorType::Mutable { types } => types
+                .iter()
+                .map(|member| {
+                    let ident = format!("{}_{}", self.ident, member.name().to_snake_case());
+                    self.member_declaration(module, set_id, &ident, member)
+                })
+                .collect(),
+            descriptor_type => {
+                vec![self.member_declaration(module, set_id, &self.ident, descriptor_type)]
+            }
+        };
+        for alias in self.aliases.iter() {
+            declarations.push(self.member_declaration(
+                module,
+                set_id,
+                &alias.ident,
+                &alias.descriptor_type,
+            ));
+        }
+        declarations
+    }
+
+    fn member_declaration(
+        &self,
+        module: &PlayoutModule,
+        set_id: u32,
+        ident: &str,
+        descriptor_type: &crate::DescriptorType,
+    ) -> glsl::syntax::Declaration {
         let mut layout_qualifier = glsl::syntax::LayoutQualifier {
             ids: NonEmpty::from_non_empty_iter([
                 glsl::syntax::LayoutQualifierSpec::Identifier(
@@ -67,16 +110,31 @@ impl crate::Binding {
             .unwrap(),
         };
 
-        if let crate::DescriptorType::StorageImage { format } = self.descriptor_type {
-            layout_qualifier
-                .ids
-                .push(glsl::syntax::LayoutQualifierSpec::Identifier(
-                    format.to_layout_qualifier().into(),
-                    None,
-                ));
+        match *descriptor_type {
+            crate::DescriptorType::StorageImage {
+                format: Some(format),
+                ..
+            }
+            | crate::DescriptorType::StorageTexelBuffer { format } => {
+                layout_qualifier
+                    .ids
+                    .push(glsl::syntax::LayoutQualifierSpec::Identifier(
+                        format.to_layout_qualifier().into(),
+                        None,
+                    ));
+            }
+            crate::DescriptorType::InputAttachment { index, .. } => {
+                layout_qualifier
+                    .ids
+                    .push(glsl::syntax::LayoutQualifierSpec::Identifier(
+                        "input_attachment_index".into(),
+                        Some(Box::new(glsl::syntax::Expr::UIntConst(index))),
+                    ));
+            }
+            _ => (),
         }
 
-        if let Some(layout) = &self.layout {
+        if let (Some(layout), Some(_)) = (&self.layout, descriptor_type.block_type()) {
             layout_qualifier
                 .ids
                 .push(glsl::syntax::LayoutQualifierSpec::Identifier(
@@ -92,30 +150,64 @@ impl crate::Binding {
             .unwrap(),
         };
 
-        match self.descriptor_type {
-            crate::DescriptorType::Sampler => todo!(),
-            crate::DescriptorType::StorageImage { .. }
-            | crate::DescriptorType::SampledImage
-            | crate::DescriptorType::CombinedImageSampler
-            | crate::DescriptorType::AccelerationStructure
-            | crate::DescriptorType::UniformBuffer { .. }
-            | crate::DescriptorType::InlineUniformBlock { .. } => {
+        for (qualifier, storage) in [
+            (
+                crate::MemoryQualifiers::READONLY,
+                glsl::syntax::StorageQualifier::ReadOnly,
+            ),
+            (
+                crate::MemoryQualifiers::WRITEONLY,
+                glsl::syntax::StorageQualifier::WriteOnly,
+            ),
+            (
+                crate::MemoryQualifiers::COHERENT,
+                glsl::syntax::StorageQualifier::Coherent,
+            ),
+            (
+                crate::MemoryQualifiers::VOLATILE,
+                glsl::syntax::StorageQualifier::Volatile,
+            ),
+            (
+                crate::MemoryQualifiers::RESTRICT,
+                glsl::syntax::StorageQualifier::Restrict,
+            ),
+        ] {
+            if descriptor_type.is_writable() && self.memory.contains(qualifier) {
+                type_qualifier
+                    .qualifiers
+                    .push(glsl::syntax::TypeQualifierSpec::Storage(storage));
+            }
+        }
+
+        match *descriptor_type {
+            crate::DescriptorType::StorageBuffer { .. }
+            | crate::DescriptorType::StorageBufferDynamic { .. } => {
                 type_qualifier
                     .qualifiers
                     .push(glsl::syntax::TypeQualifierSpec::Storage(
-                        glsl::syntax::StorageQualifier::Uniform,
+                        glsl::syntax::StorageQualifier::Buffer,
                     ));
             }
-            crate::DescriptorType::StorageBuffer { .. } => {
+            _ => {
                 type_qualifier
                     .qualifiers
                     .push(glsl::syntax::TypeQualifierSpec::Storage(
-                        glsl::syntax::StorageQualifier::Buffer,
+                        glsl::syntax::StorageQualifier::Uniform,
                     ));
             }
         }
 
-        let array_specifier = if self.descriptor_count > 1 {
+        let array_specifier = if self
+            .flags
+            .contains(crate::BindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+        {
+            Some(glsl::syntax::ArraySpecifier {
+                dimensions: NonEmpty::from_non_empty_iter([
+                    glsl::syntax::ArraySpecifierDimension::Unsized,
+                ])
+                .unwrap(),
+            })
+        } else if self.descriptor_count > 1 {
             Some(glsl::syntax::ArraySpecifier {
                 dimensions: NonEmpty::from_non_empty_iter([
                     glsl::syntax::ArraySpecifierDimension::ExplicitlySized(Box::new(
@@ -128,58 +220,132 @@ impl crate::Binding {
             None
         };
 
-        let type_specifier = match &self.descriptor_type {
-            crate::DescriptorType::Sampler => todo!(),
-            crate::DescriptorType::StorageImage { format } => {
+        let type_specifier = match descriptor_type {
+            crate::DescriptorType::Sampler { shadow } => {
+                glsl::syntax::TypeSpecifierNonArray::TypeName(
+                    if *shadow { "samplerShadow" } else { "sampler" }.into(),
+                )
+            }
+            crate::DescriptorType::UniformTexelBuffer { format } => {
                 use crate::ImageFormatDataMode::*;
                 match format.data_mode() {
-                    Float | UNorm | SNorm => glsl::syntax::TypeSpecifierNonArray::Image2D,
-                    crate::ImageFormatDataMode::SInt => {
-                        glsl::syntax::TypeSpecifierNonArray::IImage2D
-                    }
-                    crate::ImageFormatDataMode::UInt => {
-                        glsl::syntax::TypeSpecifierNonArray::UImage2D
-                    }
+                    Float | UNorm | SNorm => glsl::syntax::TypeSpecifierNonArray::SamplerBuffer,
+                    SInt => glsl::syntax::TypeSpecifierNonArray::ISamplerBuffer,
+                    UInt => glsl::syntax::TypeSpecifierNonArray::USamplerBuffer,
                 }
             }
-            crate::DescriptorType::SampledImage => {
-                glsl::syntax::TypeSpecifierNonArray::TypeName("texture2D".into())
+            crate::DescriptorType::StorageTexelBuffer { format } if format.is_64_bit() => {
+                glsl::syntax::TypeSpecifierNonArray::TypeName(
+                    format!("{}imageBuffer", format.to_type_prefix()).into(),
+                )
             }
-            crate::DescriptorType::CombinedImageSampler => {
-                glsl::syntax::TypeSpecifierNonArray::TypeName("sampler2D".into())
+            crate::DescriptorType::StorageTexelBuffer { format } => {
+                use crate::ImageFormatDataMode::*;
+                match format.data_mode() {
+                    Float | UNorm | SNorm => glsl::syntax::TypeSpecifierNonArray::ImageBuffer,
+                    SInt => glsl::syntax::TypeSpecifierNonArray::IImageBuffer,
+                    UInt => glsl::syntax::TypeSpecifierNonArray::UImageBuffer,
+                }
             }
+            crate::DescriptorType::InputAttachment {
+                component,
+                multisampled,
+                ..
+            } => glsl::syntax::TypeSpecifierNonArray::TypeName(
+                format!(
+                    "{}subpassInput{}",
+                    component.to_type_prefix(),
+                    if *multisampled { "MS" } else { "" }
+                )
+                .into(),
+            ),
+            crate::DescriptorType::StorageImage {
+                format,
+                component,
+                dimension,
+            } => glsl::syntax::TypeSpecifierNonArray::TypeName(
+                format!(
+                    "{}image{}",
+                    match format {
+                        Some(format) => format.to_type_prefix(),
+                        None => component.to_type_prefix(),
+                    },
+                    dimension.to_type_suffix()
+                )
+                .into(),
+            ),
+            crate::DescriptorType::SampledImage {
+                dimension,
+                component,
+            } => glsl::syntax::TypeSpecifierNonArray::TypeName(
+                format!(
+                    "{}texture{}",
+                    component.to_type_prefix(),
+                    dimension.to_type_suffix()
+                )
+                .into(),
+            ),
+            crate::DescriptorType::CombinedImageSampler {
+                dimension,
+                component,
+                shadow,
+            } => glsl::syntax::TypeSpecifierNonArray::TypeName(
+                format!(
+                    "{}sampler{}{}",
+                    component.to_type_prefix(),
+                    dimension.to_type_suffix(),
+                    if *shadow { "Shadow" } else { "" }
+                )
+                .into(),
+            ),
             crate::DescriptorType::AccelerationStructure => {
                 glsl::syntax::TypeSpecifierNonArray::TypeName("accelerationStructureEXT".into())
             }
+            crate::DescriptorType::Mutable { .. } => {
+                unreachable!("Mutable descriptors can't be nested")
+            }
             crate::DescriptorType::UniformBuffer { ty }
             | crate::DescriptorType::StorageBuffer { ty }
+            | crate::DescriptorType::UniformBufferDynamic { ty }
+            | crate::DescriptorType::StorageBufferDynamic { ty }
             | crate::DescriptorType::InlineUniformBlock { ty } => {
                 let (fields, identifier): (
                     Vec<glsl::syntax::StructFieldSpecifier>,
                     Option<glsl::syntax::ArrayedIdentifier>,
-                ) = match ty {
-                    crate::Type::Path(path) => {
+                ) = match ty.resolve(module) {
+                    crate::Type::Path(path) if module.data_structs.contains_key(path) => {
                         // Specific optimization for when the type directly references a struct.
                         // Take that struct and flatten it out directly as a uniform/storage block.
+                        // Explicit offsets are only supported by the standard block layouts.
+                        let explicit_layout = self
+                            .layout
+                            .as_deref()
+                            .is_none_or(|layout| crate::BlockLayout::from_name(layout).is_some());
                         let fields = module
                             .data_structs
                             .get(path.as_str())
                             .unwrap()
                             .fields
                             .iter()
-                            .map(|field| field.to_field())
+                            .map(|field| field.to_block_member_with(module, explicit_layout))
                             .collect();
                         let identifier = Some(glsl::syntax::ArrayedIdentifier {
-                            ident: self.ident.as_str().into(),
+                            ident: ident.into(),
                             array_spec: array_specifier,
                         });
                         (fields, identifier)
                     }
-                    _ => (vec![ty.as_field(self.ident.as_str())], None),
+                    _ => {
+                        let ids = ty
+                            .matrix_layout_qualifier(module, None)
+                            .into_iter()
+                            .collect();
+                        (vec![ty.as_field_with_layout(ident, ids)], None)
+                    }
                 };
                 return glsl::syntax::Declaration::Block(glsl::syntax::Block {
                     qualifier: type_qualifier,
-                    name: self.ident.to_pascal_case().into(),
+                    name: ident.to_pascal_case().into(),
                     fields,
                     identifier,
                 });
@@ -195,7 +361,7 @@ impl crate::Binding {
                         array_specifier: None,
                     },
                 },
-                name: Some(self.ident.as_str().into()),
+                name: Some(ident.into()),
                 array_specifier,
                 initializer: None,
             },
@@ -208,49 +374,261 @@ impl crate::SetLayout {
     pub fn to_declarations<'a>(
         &'a self,
         module: &'a PlayoutModule,
-    ) -> impl ExactSizeIterator<Item = glsl::syntax::Declaration> + 'a {
+    ) -> impl Iterator<Item = glsl::syntax::Declaration> + 'a {
         let set_id = self.set;
         self.bindings
             .iter()
-            .map(move |binding| binding.to_declaration(module, set_id))
+            .flat_map(move |binding| binding.to_declarations(module, set_id))
     }
 }
 
 impl crate::PlayoutModule {
-    pub fn show(&self, writer: &mut impl std::fmt::Write) {
-        let mut types_to_declare: Vec<String> = Vec::new();
-        let mut types_seen: BTreeSet<String> = BTreeSet::new();
-        for decl in self.descriptor_sets.iter() {
-            for binding in decl.bindings.iter() {
-                match &binding.descriptor_type {
-                    crate::DescriptorType::UniformBuffer { ty }
-                    | crate::DescriptorType::StorageBuffer { ty } => {
-                        if let Some(ty) = ty.base_nonprimitive_type() {
-                            if types_seen.insert(ty.clone()) {
-                                types_to_declare.push(ty.clone());
-                            }
-                        }
-                    }
-                    _ => (),
+    /// GLSL extensions the generated declarations depend on.
+    pub fn glsl_extensions(&self) -> Vec<&'static str> {
+        let mut extensions = Vec::new();
+        let bindings = || {
+            self.descriptor_sets
+                .iter()
+                .flat_map(|set| set.bindings.iter())
+        };
+        let (types_to_declare, pointees) = self.types_to_declare();
+        let mut used_types = self.glsl_root_types();
+        used_types.extend(pointees.iter().copied());
+        for ty in types_to_declare {
+            if let Some(alias) = self.type_aliases.get(ty) {
+                used_types.push(&alias.ty);
+            } else if let Some(data_struct) = self.data_structs.get(ty) {
+                used_types.extend(data_struct.fields.iter().map(|field| &field.ty));
+            }
+        }
+        let scalars: Vec<crate::PrimitiveTypeSingle> = used_types
+            .iter()
+            .filter_map(|ty| ty.scalar_type())
+            .collect();
+        let uses =
+            |types: &[crate::PrimitiveTypeSingle]| scalars.iter().any(|ty| types.contains(ty));
+        use crate::PrimitiveTypeSingle::*;
+        let int8 = uses(&[U8, I8]);
+        let int16 = uses(&[U16, I16]);
+        let float16 = uses(&[F16]);
+        if int8 {
+            extensions.push("GL_EXT_shader_explicit_arithmetic_types_int8");
+        }
+        if int16 {
+            extensions.push("GL_EXT_shader_explicit_arithmetic_types_int16");
+        }
+        let image_int64 = bindings().any(|binding| {
+            binding.declared_types().any(|member| match member {
+                crate::DescriptorType::StorageImage {
+                    format: Some(format),
+                    ..
+                }
+                | crate::DescriptorType::StorageTexelBuffer { format } => format.is_64_bit(),
+                _ => false,
+            })
+        });
+        // 64-bit images are read and written as 64-bit integers.
+        if uses(&[U64, I64]) || image_int64 {
+            extensions.push("GL_EXT_shader_explicit_arithmetic_types_int64");
+        }
+        if float16 {
+            extensions.push("GL_EXT_shader_explicit_arithmetic_types_float16");
+        }
+        // Every declared type ends up in a buffer or push constant block.
+        if int8 {
+            extensions.push("GL_EXT_shader_8bit_storage");
+        }
+        if int16 || float16 {
+            extensions.push("GL_EXT_shader_16bit_storage");
+        }
+        if bindings().any(|binding| {
+            binding.declared_types().any(|member| {
+                member.block_type().is_some()
+                    && binding.block_layout_of(member) == crate::BlockLayout::Scalar
+            })
+        }) {
+            extensions.push("GL_EXT_scalar_block_layout");
+        }
+        if bindings().any(|binding| {
+            binding
+                .flags
+                .contains(crate::BindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+        }) {
+            extensions.push("GL_EXT_nonuniform_qualifier");
+        }
+        if !pointees.is_empty() {
+            extensions.push("GL_EXT_buffer_reference");
+        }
+        if self
+            .required_features()
+            .contains(crate::DeviceFeatures::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT)
+        {
+            extensions.push("GL_EXT_shader_image_load_formatted");
+        }
+        if image_int64 {
+            extensions.push("GL_EXT_shader_image_int64");
+        }
+        // Ray tracing stages see acceleration structures through GL_EXT_ray_tracing, other
+        // stages through ray queries.
+        let ray_tracing_stages = crate::ShaderStages::RAYGEN
+            | crate::ShaderStages::ANY_HIT
+            | crate::ShaderStages::CLOSEST_HIT
+            | crate::ShaderStages::MISS
+            | crate::ShaderStages::INTERSECTION
+            | crate::ShaderStages::CALLABLE;
+        let acceleration_structure_stages = bindings()
+            .filter(|binding| {
+                binding
+                    .declared_types()
+                    .any(|member| matches!(member, crate::DescriptorType::AccelerationStructure))
+            })
+            .fold(crate::ShaderStages::empty(), |stages, binding| {
+                stages | binding.stages
+            });
+        if acceleration_structure_stages.intersects(ray_tracing_stages) {
+            extensions.push("GL_EXT_ray_tracing");
+        }
+        if !acceleration_structure_stages
+            .difference(ray_tracing_stages)
+            .is_empty()
+        {
+            extensions.push("GL_EXT_ray_query");
+        }
+        extensions
+    }
+
+    /// Types referenced directly by bindings and push constants.
+    fn glsl_root_types(&self) -> Vec<&crate::Type> {
+        let mut roots: Vec<&crate::Type> = self
+            .descriptor_sets
+            .iter()
+            .flat_map(|set| set.bindings.iter())
+            .flat_map(|binding| binding.declared_types())
+            .filter_map(|member| member.block_type())
+            .collect();
+        roots.extend(
+            self.push_constants
+                .fields
+                .iter()
+                .map(|field| &field.field.ty),
+        );
+        roots
+    }
+
+    /// Named types to declare, ordered so that each comes after the types it contains, and the
+    /// pointees that need `buffer_reference` blocks.
+    fn types_to_declare(&self) -> (Vec<&String>, Vec<&crate::Type>) {
+        let roots = self.glsl_root_types();
+        let mut types_to_declare: Vec<&String> = Vec::new();
+        let mut types_seen: BTreeSet<&str> = BTreeSet::new();
+        for ty in roots.iter() {
+            if let Some(ty) = ty.base_nonprimitive_type() {
+                self.declare_type(ty, &mut types_seen, &mut types_to_declare);
+            }
+        }
+
+        // Pointees may themselves contain pointers, so keep scanning newly declared types.
+        let mut pointees: Vec<&crate::Type> = Vec::new();
+        let mut pointees_seen: BTreeSet<String> = BTreeSet::new();
+        let mut pending = roots;
+        let mut scanned = 0;
+        loop {
+            for ty in types_to_declare[scanned..].iter() {
+                if let Some(alias) = self.type_aliases.get(*ty) {
+                    pending.push(&alias.ty);
+                } else if let Some(data_struct) = self.data_structs.get(*ty) {
+                    pending.extend(data_struct.fields.iter().map(|field| &field.ty));
+                }
+            }
+            scanned = types_to_declare.len();
+            let Some(ty) = pending.pop() else {
+                break;
+            };
+            let Some(pointee) = ty.pointee() else {
+                continue;
+            };
+            if pointees_seen.insert(pointee.pointer_block_name()) {
+                pointees.push(pointee);
+                pending.push(pointee);
+                if let Some(ty) = pointee.base_nonprimitive_type() {
+                    self.declare_type(ty, &mut types_seen, &mut types_to_declare);
                 }
             }
         }
+        (types_to_declare, pointees)
+    }
 
-        let mut queue: Vec<String> = types_to_declare.clone();
-        while let Some(next) = queue.pop() {
-            let data_struct = self.data_structs.get(&next).unwrap();
+    /// Adds `ty` to `order` after the types it contains by value.
+    fn declare_type<'a>(
+        &'a self,
+        ty: &'a String,
+        seen: &mut BTreeSet<&'a str>,
+        order: &mut Vec<&'a String>,
+    ) {
+        if !seen.insert(ty) {
+            return;
+        }
+        if let Some(alias) = self.type_aliases.get(ty) {
+            if let Some(ty) = alias.ty.base_nonprimitive_type() {
+                self.declare_type(ty, seen, order);
+            }
+        } else if let Some(data_struct) = self.data_structs.get(ty) {
             for field in data_struct.fields.iter() {
                 if let Some(ty) = field.ty.base_nonprimitive_type() {
-                    if types_seen.insert(ty.clone()) {
-                        types_to_declare.push(ty.clone());
-                        queue.push(ty.clone());
-                    }
+                    self.declare_type(ty, seen, order);
                 }
             }
         }
-        for ty in types_to_declare.iter().rev() {
-            let data_struct = self.data_structs.get(ty).unwrap();
+        order.push(ty);
+    }
+
+    /// Writes the `#extension` directives and declarations for the module.
+    pub fn show(&self, writer: &mut impl std::fmt::Write) {
+        self.show_extensions(writer);
+        self.show_declarations(writer);
+    }
+
+    /// Writes `#extension ... : require` for each of `glsl_extensions`.
+    pub fn show_extensions(&self, writer: &mut impl std::fmt::Write) {
+        for extension in self.glsl_extensions() {
+            glsl::transpiler::glsl::show_preprocessor_extension(
+                writer,
+                &glsl::syntax::PreprocessorExtension {
+                    name: glsl::syntax::PreprocessorExtensionName::Specific(extension.into()),
+                    behavior: Some(glsl::syntax::PreprocessorExtensionBehavior::Require),
+                },
+            );
+        }
+    }
+
+    /// Writes the declarations without `#extension` directives, for shaders that enable
+    /// extensions themselves.
+    pub fn show_declarations(&self, writer: &mut impl std::fmt::Write) {
+        let (types_to_declare, pointees) = self.types_to_declare();
+        for pointee in pointees.iter() {
+            pointee.show_pointer_forward_declaration(self, writer);
+        }
+        for ty in types_to_declare.iter() {
+            if let Some(data_enum) = self.data_enums.get(*ty) {
+                for define in data_enum.to_defines() {
+                    glsl::transpiler::glsl::show_preprocessor_define(writer, &define);
+                }
+                continue;
+            }
+            if let Some(alias) = self.type_aliases.get(*ty) {
+                glsl::transpiler::glsl::show_preprocessor_define(writer, &alias.to_define());
+                continue;
+            }
+            let data_struct = self.data_structs.get(*ty).unwrap();
             glsl::transpiler::glsl::show_struct(writer, &data_struct.to_struct_specifier());
+            for field in data_struct.fields.iter() {
+                for function in field.to_bitfield_accessors(&data_struct.ident) {
+                    glsl::transpiler::glsl::show_function_definition(writer, &function);
+                }
+            }
+        }
+        for pointee in pointees.iter() {
+            glsl::transpiler::glsl::show_declaration(writer, &pointee.to_pointer_block(self));
         }
         for decl in self
             .descriptor_sets
@@ -261,93 +639,374 @@ impl crate::PlayoutModule {
         }
 
         if !self.push_constants.fields.is_empty() {
-            let decl = self.push_constants.to_declaration();
+            for field in self.push_constants.fields.iter() {
+                for function in field.field.to_bitfield_accessors(&self.push_constants.name) {
+                    glsl::transpiler::glsl::show_function_definition(writer, &function);
+                }
+            }
+            let decl = self.push_constants.to_declaration(self);
             glsl::transpiler::glsl::show_declaration(writer, &decl);
         }
     }
 }
 
 impl crate::Field {
+    /// A member of a plain struct, which can't have layout qualifiers.
     pub fn to_field(&self) -> glsl::syntax::StructFieldSpecifier {
-        self.ty.as_field(self.ident.as_ref().unwrap().as_str())
+        self.to_field_with_layout(Vec::new())
     }
-}
 
-impl crate::Type {
-    pub fn base_nonprimitive_type(&self) -> Option<&String> {
-        use crate::Type::*;
-        match self {
-            Primitive(_) => None,
-            Array { ty, .. } => ty.base_nonprimitive_type(),
-            Slice { ty } => ty.base_nonprimitive_type(),
-            Path(path) => Some(path),
+    /// Like `to_field`, for members of uniform, storage and push constant blocks where explicit
+    /// offsets, alignments and matrix layouts can be expressed.
+    pub fn to_block_member(&self, module: &PlayoutModule) -> glsl::syntax::StructFieldSpecifier {
+        self.to_block_member_with(module, true)
+    }
+
+    /// Block members of blocks without a standard layout only take matrix layouts.
+    fn to_block_member_with(
+        &self,
+        module: &PlayoutModule,
+        explicit_layout: bool,
+    ) -> glsl::syntax::StructFieldSpecifier {
+        let mut ids = Vec::new();
+        if let Some(qualifier) = self.ty.matrix_layout_qualifier(module, self.matrix_layout) {
+            ids.push(qualifier);
+        }
+        if !explicit_layout {
+            return self.to_field_with_layout(ids);
         }
+        if let Some(offset) = self.offset {
+            ids.push(glsl::syntax::LayoutQualifierSpec::Identifier(
+                "offset".into(),
+                Some(Box::new(glsl::syntax::Expr::UIntConst(offset as u32))),
+            ));
+        }
+        let struct_align = match self.ty.resolve(module) {
+            crate::Type::Path(path) => module.data_structs.get(path).and_then(|s| s.align),
+            _ => None,
+        };
+        if let Some(align) = self.align.max(struct_align) {
+            ids.push(glsl::syntax::LayoutQualifierSpec::Identifier(
+                "align".into(),
+                Some(Box::new(glsl::syntax::Expr::UIntConst(align as u32))),
+            ));
+        }
+        self.to_field_with_layout(ids)
+    }
+
+    fn to_field_with_layout(
+        &self,
+        ids: Vec<glsl::syntax::LayoutQualifierSpec>,
+    ) -> glsl::syntax::StructFieldSpecifier {
+        self.ty
+            .as_field_with_layout(self.ident.as_ref().unwrap().as_str(), ids)
     }
+
+    /// Getter and setter functions for each member of a bitfield, operating on the packed integer.
+    /// For bitfield `packed` on struct `Foo`, emits `foo_packed_get_kind(packed)`
+    /// and `foo_packed_set_kind(packed, value)` which returns the updated integer.
+    pub fn to_bitfield_accessors(&self, owner: &str) -> Vec<glsl::syntax::FunctionDefinition> {
+        use glsl::syntax::{BinaryOp, Expr};
+        let crate::Type::Bitfield { ty, members } = &self.ty else {
+            return Vec::new();
+        };
+        let ty_specifier = ty.to_type_specifier_non_array();
+        let ty_mask = (1_u64 << (ty.layout().size() * 8)) - 1;
+        let constant = |value: u64| -> Expr {
+            let value = Expr::UIntConst((value & ty_mask) as u32);
+            if matches!(ty, crate::PrimitiveTypeSingle::U32) {
+                value
+            } else {
+                // No literal suffix for 8 and 16 bit integers
+                let mut name = String::new();
+                glsl::transpiler::glsl::show_type_specifier_non_array(&mut name, &ty_specifier);
+                Expr::FunCall(
+                    glsl::syntax::FunIdentifier::Identifier(name.as_str().into()),
+                    vec![value],
+                )
+            }
+        };
+        let function =
+            |name: String, parameters: &[&str], expr: Expr| glsl::syntax::FunctionDefinition {
+                prototype: glsl::syntax::FunctionPrototype {
+                    ty: ty_specifier.clone().into(),
+                    name: name.into(),
+                    parameters: parameters
+                        .iter()
+                        .map(|param| {
+                            glsl::syntax::FunctionParameterDeclaration::new_named(
+                                *param,
+                                ty_specifier.clone(),
+                            )
+                        })
+                        .collect(),
+                },
+                statement: glsl::syntax::CompoundStatement {
+                    statement_list: vec![glsl::syntax::Statement::Simple(Box::new(
+                        glsl::syntax::SimpleStatement::Jump(glsl::syntax::JumpStatement::Return(
+                            Some(Box::new(expr)),
+                        )),
+                    ))],
+                },
+            };
+        let prefix = format!("{}_{}", owner.to_snake_case(), self.ident.as_ref().unwrap());
+        let packed = || Box::new(Expr::Variable("packed".into()));
+        let mut functions = Vec::with_capacity(members.len() * 2);
+        for member in members.iter() {
+            let offset = || Box::new(Expr::UIntConst(member.offset));
+            // (packed >> offset) & mask
+            functions.push(function(
+                format!("{}_get_{}", prefix, member.ident),
+                &["packed"],
+                Expr::Binary(
+                    BinaryOp::BitAnd,
+                    Box::new(Expr::Binary(BinaryOp::RShift, packed(), offset())),
+                    Box::new(constant(member.mask())),
+                ),
+            ));
+            // (packed & ~(mask << offset)) | ((value & mask) << offset)
+            functions.push(function(
+                format!("{}_set_{}", prefix, member.ident),
+                &["packed", "value"],
+                Expr::Binary(
+                    BinaryOp::BitOr,
+                    Box::new(Expr::Binary(
+                        BinaryOp::BitAnd,
+                        packed(),
+                        Box::new(constant(!(member.mask() << member.offset))),
+                    )),
+                    Box::new(Expr::Binary(
+                        BinaryOp::LShift,
+                        Box::new(Expr::Binary(
+                            BinaryOp::BitAnd,
+                            Box::new(Expr::Variable("value".into())),
+                            Box::new(constant(member.mask())),
+                        )),
+                        offset(),
+                    )),
+                ),
+            ));
+        }
+        functions
+    }
+}
+
+impl crate::Type {
     pub fn base_type(&self) -> glsl::syntax::TypeSpecifierNonArray {
         use crate::Type::*;
         match self {
             Primitive(ty) => ty.to_type_specifier_non_array(),
+            Bitfield { ty, .. } => ty.to_type_specifier_non_array(),
             Array { ty, .. } => ty.base_type(),
             Slice { ty } => ty.base_type(),
             Path(path) => glsl::syntax::TypeSpecifierNonArray::TypeName(path.clone().into()),
+            Ptr { .. } => glsl::syntax::TypeSpecifierNonArray::TypeName(
+                self.pointee().unwrap().pointer_block_name().into(),
+            ),
+        }
+    }
+    /// The scalar type of primitives, bitfields and arrays of them. Named types and pointers
+    /// have none of their own.
+    fn scalar_type(&self) -> Option<crate::PrimitiveTypeSingle> {
+        use crate::Type::*;
+        match self {
+            Primitive(
+                crate::PrimitiveType::Single(ty)
+                | crate::PrimitiveType::Vec { ty, .. }
+                | crate::PrimitiveType::Mat { ty, .. },
+            ) => Some(*ty),
+            Bitfield { ty, .. } => Some(*ty),
+            Array { ty, .. } | Slice { ty } => ty.scalar_type(),
+            Path(_) | Ptr { .. } => None,
+        }
+    }
+    /// Name of the `buffer_reference` block for pointers to `self`.
+    pub fn pointer_block_name(&self) -> String {
+        let mut name = String::new();
+        glsl::transpiler::glsl::show_type_specifier_non_array(&mut name, &self.base_type());
+        name.push_str("Ptr");
+        name
+    }
+    fn pointer_block_qualifier(
+        &self,
+        module: &PlayoutModule,
+        with_layout: bool,
+    ) -> glsl::syntax::TypeQualifier {
+        let mut ids = vec![glsl::syntax::LayoutQualifierSpec::Identifier(
+            "buffer_reference".into(),
+            None,
+        )];
+        if with_layout {
+            let align = self
+                .layout_in(
+                    module,
+                    crate::BlockLayout::Std430,
+                    crate::MatrixLayout::default(),
+                )
+                .unwrap()
+                .align();
+            ids.push(glsl::syntax::LayoutQualifierSpec::Identifier(
+                "std430".into(),
+                None,
+            ));
+            ids.push(glsl::syntax::LayoutQualifierSpec::Identifier(
+                "buffer_reference_align".into(),
+                Some(Box::new(glsl::syntax::Expr::UIntConst(align as u32))),
+            ));
+        }
+        glsl::syntax::TypeQualifier {
+            qualifiers: NonEmpty::from_non_empty_iter([
+                glsl::syntax::TypeQualifierSpec::Layout(glsl::syntax::LayoutQualifier {
+                    ids: NonEmpty::from_non_empty_iter(ids).unwrap(),
+                }),
+                glsl::syntax::TypeQualifierSpec::Storage(glsl::syntax::StorageQualifier::Buffer),
+            ])
+            .unwrap(),
+        }
+    }
+    /// Forward declaration of the `buffer_reference` block for pointers to `self`, so that
+    /// structs can contain pointers to types declared after them.
+    pub fn show_pointer_forward_declaration(
+        &self,
+        module: &PlayoutModule,
+        writer: &mut impl std::fmt::Write,
+    ) {
+        glsl::transpiler::glsl::show_type_qualifier(
+            writer,
+            &self.pointer_block_qualifier(module, false),
+        );
+        let _ = writeln!(writer, " {};", self.pointer_block_name());
+    }
+    /// The `buffer_reference` block for pointers to `self`, indexed as `ptr.data[i]`.
+    pub fn to_pointer_block(&self, module: &PlayoutModule) -> glsl::syntax::Declaration {
+        let field = self.as_field_with_layout(
+            "data",
+            self.matrix_layout_qualifier(module, None)
+                .into_iter()
+                .collect(),
+        );
+        glsl::syntax::Declaration::Block(glsl::syntax::Block {
+            qualifier: self.pointer_block_qualifier(module, true),
+            name: self.pointer_block_name().into(),
+            fields: vec![glsl::syntax::StructFieldSpecifier {
+                qualifier: field.qualifier,
+                ty: glsl::syntax::TypeSpecifier {
+                    ty: self.base_type(),
+                    array_specifier: None,
+                },
+                identifiers: NonEmpty::from_non_empty_iter([glsl::syntax::ArrayedIdentifier {
+                    ident: "data".into(),
+                    array_spec: Some(glsl::syntax::ArraySpecifier {
+                        dimensions: NonEmpty::from_non_empty_iter([
+                            glsl::syntax::ArraySpecifierDimension::Unsized,
+                        ])
+                        .unwrap(),
+                    }),
+                }])
+                .unwrap(),
+            }],
+            identifier: None,
+        })
+    }
+    /// `row_major` or `column_major` for a block member of this type, which also applies to
+    /// the matrices of the structs it contains. `None` for types without matrices, or whose
+    /// matrices implicitly use the default column major layout.
+    fn matrix_layout_qualifier(
+        &self,
+        module: &PlayoutModule,
+        explicit: Option<crate::MatrixLayout>,
+    ) -> Option<glsl::syntax::LayoutQualifierSpec> {
+        let layout = self
+            .matrix_layout_in(module, explicit.unwrap_or_default())
+            .ok()
+            .flatten()?;
+        let qualifier = match layout {
+            crate::MatrixLayout::ColumnMajor if explicit.is_none() => return None,
+            crate::MatrixLayout::ColumnMajor => "column_major",
+            crate::MatrixLayout::RowMajor => "row_major",
+        };
+        Some(glsl::syntax::LayoutQualifierSpec::Identifier(
+            qualifier.into(),
+            None,
+        ))
+    }
+    pub fn array_specifier(&self) -> Option<glsl::syntax::ArraySpecifier> {
+        match self {
+            crate::Type::Array { size, .. } => Some(glsl::syntax::ArraySpecifier {
+                dimensions: NonEmpty::from_non_empty_iter([
+                    glsl::syntax::ArraySpecifierDimension::ExplicitlySized(Box::new(
+                        glsl::syntax::Expr::UIntConst(*size as u32),
+                    )),
+                ])
+                .unwrap(),
+            }),
+            crate::Type::Primitive(_) => None,
+            crate::Type::Bitfield { .. } => None,
+            crate::Type::Path(_) => None,
+            crate::Type::Ptr { .. } => None,
+            crate::Type::Slice { .. } => Some(glsl::syntax::ArraySpecifier {
+                dimensions: NonEmpty::from_non_empty_iter([
+                    glsl::syntax::ArraySpecifierDimension::Unsized,
+                ])
+                .unwrap(),
+            }),
         }
     }
     pub fn as_field(&self, ident: &str) -> glsl::syntax::StructFieldSpecifier {
+        self.as_field_with_layout(ident, Vec::new())
+    }
+
+    fn as_field_with_layout(
+        &self,
+        ident: &str,
+        ids: Vec<glsl::syntax::LayoutQualifierSpec>,
+    ) -> glsl::syntax::StructFieldSpecifier {
         glsl::syntax::StructFieldSpecifier {
-            qualifier: None,
+            qualifier: NonEmpty::from_non_empty_iter(ids).map(|ids| glsl::syntax::TypeQualifier {
+                qualifiers: NonEmpty::from_non_empty_iter([
+                    glsl::syntax::TypeQualifierSpec::Layout(glsl::syntax::LayoutQualifier { ids }),
+                ])
+                .unwrap(),
+            }),
             ty: glsl::syntax::TypeSpecifier {
-                ty: match self {
-                    crate::Type::Path(path) => {
-                        glsl::syntax::TypeSpecifierNonArray::TypeName(path.clone().into())
-                    }
-                    _ => self.base_type(),
-                },
+                ty: self.base_type(),
                 array_specifier: None,
             },
             identifiers: NonEmpty::from_non_empty_iter([glsl::syntax::ArrayedIdentifier {
                 ident: ident.into(),
-                array_spec: match self {
-                    crate::Type::Array { size, .. } => Some(glsl::syntax::ArraySpecifier {
-                        dimensions: NonEmpty::from_non_empty_iter([
-                            glsl::syntax::ArraySpecifierDimension::ExplicitlySized(Box::new(
-                                glsl::syntax::Expr::UIntConst(*size as u32),
-                            )),
-                        ])
-                        .unwrap(),
-                    }),
-                    crate::Type::Primitive(_) => None,
-                    crate::Type::Path(_) => None,
-                    crate::Type::Slice { .. } => Some(glsl::syntax::ArraySpecifier {
-                        dimensions: NonEmpty::from_non_empty_iter([
-                            glsl::syntax::ArraySpecifierDimension::Unsized,
-                        ])
-                        .unwrap(),
-                    }),
-                },
+                array_spec: self.array_specifier(),
             }])
             .unwrap(),
         }
     }
 }
 
+impl crate::PrimitiveTypeSingle {
+    pub fn to_type_specifier_non_array(&self) -> glsl::syntax::TypeSpecifierNonArray {
+        use glsl::syntax::TypeSpecifierNonArray::*;
+        match self {
+            crate::PrimitiveTypeSingle::U8 => TypeName("uint8_t".into()),
+            crate::PrimitiveTypeSingle::U16 => TypeName("uint16_t".into()),
+            crate::PrimitiveTypeSingle::U32 => UInt,
+            crate::PrimitiveTypeSingle::U64 => TypeName("uint64_t".into()),
+            crate::PrimitiveTypeSingle::I8 => TypeName("int8_t".into()),
+            crate::PrimitiveTypeSingle::I16 => TypeName("int16_t".into()),
+            crate::PrimitiveTypeSingle::I32 => Int,
+            crate::PrimitiveTypeSingle::I64 => TypeName("int64_t".into()),
+            crate::PrimitiveTypeSingle::F16 => TypeName("float16_t".into()),
+            crate::PrimitiveTypeSingle::F32 => Float,
+            crate::PrimitiveTypeSingle::F64 => Double,
+            crate::PrimitiveTypeSingle::Bool => Bool,
+        }
+    }
+}
+
 impl crate::PrimitiveType {
     pub fn to_type_specifier_non_array(&self) -> glsl::syntax::TypeSpecifierNonArray {
         use glsl::syntax::TypeSpecifierNonArray::*;
         match self {
-            crate::PrimitiveType::Single(ty) => match ty {
-                crate::PrimitiveTypeSingle::U8 => TypeName("uint8_t".into()),
-                crate::PrimitiveTypeSingle::U16 => TypeName("uint16_t".into()),
-                crate::PrimitiveTypeSingle::U32 => UInt,
-                crate::PrimitiveTypeSingle::U64 => TypeName("uint64_t".into()),
-                crate::PrimitiveTypeSingle::I8 => TypeName("int8_t".into()),
-                crate::PrimitiveTypeSingle::I16 => TypeName("int16_t".into()),
-                crate::PrimitiveTypeSingle::I32 => Int,
-                crate::PrimitiveTypeSingle::I64 => TypeName("int64_t".into()),
-                crate::PrimitiveTypeSingle::F16 => TypeName("float16_t".into()),
-                crate::PrimitiveTypeSingle::F32 => Float,
-                crate::PrimitiveTypeSingle::F64 => Double,
-                crate::PrimitiveTypeSingle::Bool => Bool,
-            },
+            crate::PrimitiveType::Single(ty) => ty.to_type_specifier_non_array(),
+            crate::PrimitiveType::Vec { ty, length: 1 } => ty.to_type_specifier_non_array(),
             crate::PrimitiveType::Vec { ty, length } => match (ty, length) {
                 (crate::PrimitiveTypeSingle::U8, 2) => TypeName("u8vec2".into()),
                 (crate::PrimitiveTypeSingle::U8, 3) => TypeName("u8vec3".into()),
@@ -388,19 +1047,31 @@ impl crate::PrimitiveType {
                 (crate::PrimitiveTypeSingle::Bool, 2) => BVec2,
                 (crate::PrimitiveTypeSingle::Bool, 3) => BVec3,
                 (crate::PrimitiveTypeSingle::Bool, 4) => BVec4,
-                _ => panic!(),
+                _ => panic!("Vectors must have 1 to 4 components"),
             },
-            crate::PrimitiveType::Mat { ty, rows, columns } => match (ty, rows, columns) {
-                (crate::PrimitiveTypeSingle::F16, 2, 2) => TypeName("f16mat2x2".into()),
-                (crate::PrimitiveTypeSingle::F16, 3, 3) => TypeName("f16mat3x3".into()),
-                (crate::PrimitiveTypeSingle::F16, 4, 4) => TypeName("f16mat4x4".into()),
+            crate::PrimitiveType::Mat { ty, rows, columns } => match (ty, columns, rows) {
                 (crate::PrimitiveTypeSingle::F32, 2, 2) => Mat2,
+                (crate::PrimitiveTypeSingle::F32, 2, 3) => Mat23,
+                (crate::PrimitiveTypeSingle::F32, 2, 4) => Mat24,
+                (crate::PrimitiveTypeSingle::F32, 3, 2) => Mat32,
                 (crate::PrimitiveTypeSingle::F32, 3, 3) => Mat3,
+                (crate::PrimitiveTypeSingle::F32, 3, 4) => Mat34,
+                (crate::PrimitiveTypeSingle::F32, 4, 2) => Mat42,
+                (crate::PrimitiveTypeSingle::F32, 4, 3) => Mat43,
                 (crate::PrimitiveTypeSingle::F32, 4, 4) => Mat4,
-                (crate::PrimitiveTypeSingle::F64, 2, 2) => TypeName("f64mat2x2".into()),
-                (crate::PrimitiveTypeSingle::F64, 3, 3) => TypeName("f64mat3x3".into()),
-                (crate::PrimitiveTypeSingle::F64, 4, 4) => TypeName("f64mat4x4".into()),
-                _ => panic!(),
+                (crate::PrimitiveTypeSingle::F64, 2, 2) => DMat2,
+                (crate::PrimitiveTypeSingle::F64, 2, 3) => DMat23,
+                (crate::PrimitiveTypeSingle::F64, 2, 4) => DMat24,
+                (crate::PrimitiveTypeSingle::F64, 3, 2) => DMat32,
+                (crate::PrimitiveTypeSingle::F64, 3, 3) => DMat3,
+                (crate::PrimitiveTypeSingle::F64, 3, 4) => DMat34,
+                (crate::PrimitiveTypeSingle::F64, 4, 2) => DMat42,
+                (crate::PrimitiveTypeSingle::F64, 4, 3) => DMat43,
+                (crate::PrimitiveTypeSingle::F64, 4, 4) => DMat4,
+                (crate::PrimitiveTypeSingle::F16, 2..=4, 2..=4) => {
+                    TypeName(format!("f16mat{}x{}", columns, rows).into())
+                }
+                _ => panic!("Matrices must have 2 to 4 floating point rows and columns"),
             },
         }
     }
@@ -417,8 +1088,60 @@ impl crate::DataStruct {
     }
 }
 
+impl crate::DataEnum {
+    /// GLSL has no enums. The enum name is defined as an alias of the underlying integer type,
+    /// and each variant becomes a constant of that type.
+    pub fn to_defines(&self) -> Vec<glsl::syntax::PreprocessorDefine> {
+        let mut ty = String::new();
+        glsl::transpiler::glsl::show_type_specifier_non_array(
+            &mut ty,
+            &self.ty.to_type_specifier_non_array(),
+        );
+        let mut defines = vec![glsl::syntax::PreprocessorDefine::ObjectLike {
+            ident: self.ident.as_str().into(),
+            value: ty.clone(),
+        }];
+        defines.extend(self.variants.iter().map(|variant| {
+            use crate::PrimitiveTypeSingle::*;
+            let value = match self.ty {
+                U32 => format!("{}u", variant.value),
+                I32 => format!("{}", variant.value),
+                U64 => format!("{}ul", variant.value),
+                I64 => format!("{}l", variant.value),
+                U8 | U16 => format!("{}({}u)", ty, variant.value),
+                _ => format!("{}({})", ty, variant.value),
+            };
+            glsl::syntax::PreprocessorDefine::ObjectLike {
+                ident: format!("{}_{}", self.ident, variant.ident)
+                    .to_screaming_snake_case()
+                    .into(),
+                value,
+            }
+        }));
+        defines
+    }
+}
+
+impl crate::TypeAlias {
+    /// GLSL has no typedef. Aliases are emitted as a `#define` of the aliased type.
+    pub fn to_define(&self) -> glsl::syntax::PreprocessorDefine {
+        let mut value = String::new();
+        glsl::transpiler::glsl::show_type_specifier(
+            &mut value,
+            &glsl::syntax::TypeSpecifier {
+                ty: self.ty.base_type(),
+                array_specifier: self.ty.array_specifier(),
+            },
+        );
+        glsl::syntax::PreprocessorDefine::ObjectLike {
+            ident: self.ident.as_str().into(),
+            value,
+        }
+    }
+}
+
 impl crate::PushConstantsLayout {
-    pub fn to_declaration(&self) -> glsl::syntax::Declaration {
+    pub fn to_declaration(&self, module: &PlayoutModule) -> glsl::syntax::Declaration {
         let layout_qualifier = glsl::syntax::LayoutQualifier {
             ids: NonEmpty::from_non_empty_iter([glsl::syntax::LayoutQualifierSpec::Identifier(
                 "push_constant".into(),
@@ -446,7 +1169,7 @@ impl crate::PushConstantsLayout {
             fields: self
                 .fields
                 .iter()
-                .map(|field| field.field.to_field())
+                .map(|field| field.field.to_block_member(module))
                 .collect(),
             identifier: if self.fields.len() > 1 {
                 Some(glsl::syntax::ArrayedIdentifier {
@@ -459,3 +1182,96 @@ impl crate::PushConstantsLayout {
         })
     }
 }
+
+#[cfg(all(test, feature = "parser"))]
+mod tests {
+    use crate::PlayoutModule;
+
+    fn extensions(source: &str) -> Vec<&'static str> {
+        PlayoutModule::try_from(source).unwrap().glsl_extensions()
+    }
+
+    fn show(source: &str) -> String {
+        let mut output = String::new();
+        PlayoutModule::try_from(source).unwrap().show(&mut output);
+        output
+    }
+
+    #[test]
+    fn small_storage() {
+        let source = "struct Data { a: u8, b: u16, c: f16 }
+            #[set] struct S {
+                #![stage(COMPUTE)]
+                data: StorageBuffer<Data>,
+            }";
+        assert_eq!(
+            extensions(source),
+            [
+                "GL_EXT_shader_explicit_arithmetic_types_int8",
+                "GL_EXT_shader_explicit_arithmetic_types_int16",
+                "GL_EXT_shader_explicit_arithmetic_types_float16",
+                "GL_EXT_shader_8bit_storage",
+                "GL_EXT_shader_16bit_storage",
+            ]
+        );
+        let output = show(source);
+        assert!(output.contains("#extension GL_EXT_shader_8bit_storage : require\n"));
+        assert!(output.contains("#extension GL_EXT_shader_16bit_storage : require\n"));
+    }
+
+    #[test]
+    fn image_int64() {
+        let source = "#[set] struct S {
+                #![stage(COMPUTE)]
+                counters: StorageImage<R64_UInt>,
+            }";
+        assert_eq!(
+            extensions(source),
+            [
+                "GL_EXT_shader_explicit_arithmetic_types_int64",
+                "GL_EXT_shader_image_int64",
+            ]
+        );
+        let output = show(source);
+        assert!(output.contains("#extension GL_EXT_shader_image_int64 : require\n"));
+        assert!(output.contains("u64image2D"));
+    }
+
+    #[test]
+    fn buffer_reference() {
+        let source = "struct Node { value: f32, next: Ptr<Node> }
+            #[push_constants] struct P {
+                #![stage(COMPUTE)]
+                root: Ptr<Node>,
+            }";
+        assert!(extensions(source).contains(&"GL_EXT_buffer_reference"));
+        let output = show(source);
+        assert!(output.contains("#extension GL_EXT_buffer_reference : require\n"));
+        assert!(output.contains("buffer_reference"));
+    }
+
+    #[test]
+    fn nonuniform_arrays() {
+        let source = "#[set] struct S {
+                #![stage(FRAGMENT)]
+                #[partially_bound]
+                #[variable_count = 1024]
+                textures: [SampledImage],
+            }";
+        assert_eq!(extensions(source), ["GL_EXT_nonuniform_qualifier"]);
+        let output = show(source);
+        assert!(output.contains("#extension GL_EXT_nonuniform_qualifier : require\n"));
+        assert!(output.contains("textures[]"));
+    }
+
+    #[test]
+    fn no_extensions() {
+        let source = "struct Data { a: f32, b: UVec4 }
+            #[set] struct S {
+                #![stage(COMPUTE)]
+                data: StorageBuffer<Data>,
+            }";
+        assert!(extensions(source).is_empty());
+        assert!(!show(source).contains("#extension"));
+    }
+}
@@ -0,0 +1,443 @@
+use std::alloc::Layout;
+
+use crate::{
+    Binding, DataStruct, DescriptorType, Field, MatrixLayout, PlayoutModule, PrimitiveType,
+    PrimitiveTypeSingle, PushConstantsLayout, Type,
+};
+
+/// Rules for laying out data inside a uniform block, storage block or push constant block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockLayout {
+    Std140,
+    #[default]
+    Std430,
+    /// VK_EXT_scalar_block_layout
+    Scalar,
+}
+
+impl BlockLayout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "std140" => Some(Self::Std140),
+            "std430" => Some(Self::Std430),
+            "scalar" => Some(Self::Scalar),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Std140 => "std140",
+            Self::Std430 => "std430",
+            Self::Scalar => "scalar",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutError {
+    pub message: String,
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// The layout of a struct, with the offset of each field.
+#[derive(Debug, Clone)]
+pub struct StructLayout {
+    pub layout: Layout,
+    pub offsets: Vec<usize>,
+}
+
+fn round_up(value: usize, align: usize) -> usize {
+    value.next_multiple_of(align)
+}
+
+impl Binding {
+    /// The block layout of uniform, storage and inline uniform blocks, as selected by
+    /// `#[layout = ...]` or defaulted by the descriptor type.
+    pub fn block_layout(&self) -> BlockLayout {
+        self.block_layout_of(&self.descriptor_type)
+    }
+
+    /// The block layout of a member of a mutable binding.
+    pub fn block_layout_of(&self, descriptor_type: &DescriptorType) -> BlockLayout {
+        if let Some(layout) = self.layout.as_deref().and_then(BlockLayout::from_name) {
+            return layout;
+        }
+        match descriptor_type {
+            DescriptorType::UniformBuffer { .. }
+            | DescriptorType::UniformBufferDynamic { .. }
+            | DescriptorType::InlineUniformBlock { .. } => BlockLayout::Std140,
+            _ => BlockLayout::Std430,
+        }
+    }
+}
+
+impl PrimitiveType {
+    pub fn layout(&self) -> Layout {
+        self.layout_in(BlockLayout::default(), MatrixLayout::default())
+    }
+    pub fn layout_in(&self, block: BlockLayout, major: MatrixLayout) -> Layout {
+        match self {
+            PrimitiveType::Single(ty) => ty.layout(),
+            PrimitiveType::Vec { ty, length } => {
+                let component = ty.layout();
+                let size = component.size() * *length as usize;
+                let align = match (block, length) {
+                    (BlockLayout::Scalar, _) | (_, 1) => component.align(),
+                    (_, 2) => component.align() * 2,
+                    // Three-component vectors are aligned like four-component vectors
+                    _ => component.align() * 4,
+                };
+                Layout::from_size_align(size, align).unwrap()
+            }
+            PrimitiveType::Mat { rows, columns, .. } => {
+                let stride = self.matrix_stride(block, major).unwrap();
+                let count = match major {
+                    MatrixLayout::ColumnMajor => *columns,
+                    MatrixLayout::RowMajor => *rows,
+                };
+                let align = self
+                    .matrix_vector(major)
+                    .unwrap()
+                    .layout_in(block, major)
+                    .align();
+                let align = match block {
+                    BlockLayout::Std140 => align.max(16),
+                    _ => align,
+                };
+                Layout::from_size_align(stride * count as usize, align).unwrap()
+            }
+        }
+    }
+
+    /// The column vector of a column-major matrix, or the row vector of a row-major matrix.
+    fn matrix_vector(&self, major: MatrixLayout) -> Option<PrimitiveType> {
+        match self {
+            PrimitiveType::Mat { ty, rows, columns } => Some(PrimitiveType::Vec {
+                ty: *ty,
+                length: match major {
+                    MatrixLayout::ColumnMajor => *rows,
+                    MatrixLayout::RowMajor => *columns,
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    /// Distance in bytes between two adjacent columns of a column-major matrix,
+    /// or two adjacent rows of a row-major matrix.
+    /// Matrices are laid out like an array of vectors, so the stride follows the array stride
+    /// rules of the block layout.
+    pub fn matrix_stride(&self, block: BlockLayout, major: MatrixLayout) -> Option<usize> {
+        let vector = self.matrix_vector(major)?.layout_in(block, major);
+        Some(match block {
+            BlockLayout::Std140 => round_up(vector.size(), vector.align().max(16)),
+            _ => vector.pad_to_align().size(),
+        })
+    }
+}
+
+impl Type {
+    pub fn layout(&self, module: &PlayoutModule) -> Layout {
+        self.layout_in(module, BlockLayout::default(), MatrixLayout::default())
+            .unwrap()
+    }
+    /// Layout of the type inside a block with the `block` layout rules, when its matrices default
+    /// to `major`. Runtime sized arrays have a size of 0.
+    pub fn layout_in(
+        &self,
+        module: &PlayoutModule,
+        block: BlockLayout,
+        major: MatrixLayout,
+    ) -> Result<Layout, LayoutError> {
+        let layout = match self {
+            Type::Array { ty, size } => {
+                let (stride, align) = array_stride(ty, module, block, major)?;
+                Layout::from_size_align(stride * *size, align).unwrap()
+            }
+            Type::Slice { ty } => {
+                let (_, align) = array_stride(ty, module, block, major)?;
+                Layout::from_size_align(0, align).unwrap()
+            }
+            Type::Primitive(ty) => ty.layout_in(block, major),
+            Type::Path(path) => {
+                if let Some(data_enum) = module.data_enums.get(path) {
+                    return Ok(data_enum.ty.layout());
+                }
+                if let Some(alias) = module.type_aliases.get(path) {
+                    return alias.ty.layout_in(module, block, major);
+                }
+                let data_struct = module.data_structs.get(path).ok_or_else(|| LayoutError {
+                    message: format!("Unknown type `{}`", path),
+                })?;
+                data_struct.layout_in(module, block, major)?.layout
+            }
+            Type::Bitfield { ty, .. } => ty.layout(),
+            Type::Ptr { .. } => PrimitiveTypeSingle::U64.layout(),
+        };
+        Ok(layout)
+    }
+}
+
+impl Type {
+    /// The layout shared by every matrix in the type when its matrices default to `major`, or
+    /// `None` if it has no matrices. GLSL only sets matrix layouts on block members, so all the
+    /// matrices in nested structs must agree.
+    pub fn matrix_layout_in(
+        &self,
+        module: &PlayoutModule,
+        major: MatrixLayout,
+    ) -> Result<Option<MatrixLayout>, LayoutError> {
+        match self.resolve(module) {
+            Type::Primitive(PrimitiveType::Mat { .. }) => Ok(Some(major)),
+            Type::Array { ty, .. } | Type::Slice { ty } => ty.matrix_layout_in(module, major),
+            Type::Path(path) => {
+                let Some(data_struct) = module.data_structs.get(path) else {
+                    return Ok(None);
+                };
+                let mut layout = None;
+                for field in data_struct.fields.iter() {
+                    let field_layout = field
+                        .ty
+                        .matrix_layout_in(module, field.matrix_layout.unwrap_or(major))?;
+                    match (layout, field_layout) {
+                        (Some(a), Some(b)) if a != b => {
+                            return Err(LayoutError {
+                                message: format!(
+                                    "Matrices in struct `{}` mix row and column major layouts, which GLSL can only set on block members",
+                                    data_struct.ident
+                                ),
+                            })
+                        }
+                        (None, _) => layout = field_layout,
+                        _ => (),
+                    }
+                }
+                Ok(layout)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Array stride and alignment of an array with elements of type `ty`.
+fn array_stride(
+    ty: &Type,
+    module: &PlayoutModule,
+    block: BlockLayout,
+    major: MatrixLayout,
+) -> Result<(usize, usize), LayoutError> {
+    let element = ty.layout_in(module, block, major)?;
+    let align = match block {
+        BlockLayout::Std140 => element.align().max(16),
+        _ => element.align(),
+    };
+    Ok((round_up(element.size(), align), align))
+}
+
+impl DataStruct {
+    pub fn layout(&self, module: &PlayoutModule) -> Layout {
+        self.layout_in(module, BlockLayout::default(), MatrixLayout::default())
+            .unwrap()
+            .layout
+    }
+    /// Layout of the struct inside a block with the `block` layout rules, when its matrices
+    /// default to `major` as inherited from the field referencing this struct.
+    pub fn layout_in(
+        &self,
+        module: &PlayoutModule,
+        block: BlockLayout,
+        major: MatrixLayout,
+    ) -> Result<StructLayout, LayoutError> {
+        layout_fields(
+            &self.ident,
+            self.fields.iter(),
+            self.align,
+            module,
+            block,
+            major,
+        )
+    }
+}
+
+impl PushConstantsLayout {
+    pub fn layout_in(
+        &self,
+        module: &PlayoutModule,
+        block: BlockLayout,
+    ) -> Result<StructLayout, LayoutError> {
+        layout_fields(
+            &self.name,
+            self.fields.iter().map(|field| &field.field),
+            None,
+            module,
+            block,
+            MatrixLayout::default(),
+        )
+    }
+}
+
+pub(crate) fn layout_fields<'a>(
+    name: &str,
+    fields: impl Iterator<Item = &'a Field>,
+    min_align: Option<usize>,
+    module: &PlayoutModule,
+    block: BlockLayout,
+    major: MatrixLayout,
+) -> Result<StructLayout, LayoutError> {
+    let mut end = 0;
+    let mut struct_align = min_align.unwrap_or(1);
+    let mut offsets = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_deref().unwrap_or("_");
+        let layout = field
+            .ty
+            .layout_in(module, block, field.matrix_layout.unwrap_or(major))?;
+        let align = layout.align().max(field.align.unwrap_or(1));
+        let offset = match field.offset {
+            Some(offset) if offset < end => {
+                return Err(LayoutError {
+                    message: format!(
+                        "Field `{}.{}` at offset {} overlaps the previous field ending at offset {}",
+                        name, ident, offset, end
+                    ),
+                });
+            }
+            Some(offset) if offset % align != 0 => {
+                return Err(LayoutError {
+                    message: format!(
+                        "Field `{}.{}` at offset {} must be aligned to {} bytes under the {} layout",
+                        name,
+                        ident,
+                        offset,
+                        align,
+                        block.name()
+                    ),
+                });
+            }
+            Some(offset) => offset,
+            None => round_up(end, align),
+        };
+        offsets.push(offset);
+        end = offset + layout.size();
+        struct_align = struct_align.max(align);
+    }
+    if block == BlockLayout::Std140 {
+        struct_align = struct_align.max(16);
+    }
+    Ok(StructLayout {
+        layout: Layout::from_size_align(round_up(end, struct_align), struct_align).unwrap(),
+        offsets,
+    })
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod tests {
+    use super::*;
+
+    fn layout(source: &str, block: BlockLayout) -> Result<(Vec<usize>, usize), LayoutError> {
+        let module = PlayoutModule::try_from(source).unwrap();
+        let layout = module.data_structs["S"].layout_in(&module, block, MatrixLayout::default())?;
+        Ok((layout.offsets, layout.layout.size()))
+    }
+
+    const MIXED: &str = "struct S { a: f32, b: Vec3, c: f32, d: [f32; 2], e: Mat3 }";
+
+    #[test]
+    fn std430() {
+        let (offsets, size) = layout(MIXED, BlockLayout::Std430).unwrap();
+        assert_eq!(offsets, [0, 16, 28, 32, 48]);
+        assert_eq!(size, 96);
+    }
+
+    #[test]
+    fn std140() {
+        // Arrays and structs are aligned to 16 bytes, and array elements padded to 16 bytes.
+        let (offsets, size) = layout(MIXED, BlockLayout::Std140).unwrap();
+        assert_eq!(offsets, [0, 16, 28, 32, 64]);
+        assert_eq!(size, 112);
+    }
+
+    #[test]
+    fn scalar() {
+        let (offsets, size) = layout(MIXED, BlockLayout::Scalar).unwrap();
+        assert_eq!(offsets, [0, 4, 16, 20, 28]);
+        assert_eq!(size, 64);
+    }
+
+    #[test]
+    fn row_major() {
+        let source = "struct S { a: f32, #[row_major] m: Mat3x4, b: f32 }";
+        let module = PlayoutModule::try_from(source).unwrap();
+        let Type::Primitive(mat) = &module.data_structs["S"].fields[1].ty else {
+            unreachable!()
+        };
+        assert_eq!(
+            mat.matrix_stride(BlockLayout::Std430, MatrixLayout::RowMajor),
+            Some(16)
+        );
+        // Four rows of three columns, each padded to 16 bytes
+        let (offsets, size) = layout(source, BlockLayout::Std430).unwrap();
+        assert_eq!(offsets, [0, 16, 80]);
+        assert_eq!(size, 96);
+    }
+
+    #[test]
+    fn explicit_offset_and_align() {
+        let source = "struct S { a: f32, #[offset = 32] b: Vec4, #[align = 64] c: f32, d: f32 }";
+        for block in [
+            BlockLayout::Std140,
+            BlockLayout::Std430,
+            BlockLayout::Scalar,
+        ] {
+            let (offsets, size) = layout(source, block).unwrap();
+            assert_eq!(offsets, [0, 32, 64, 68]);
+            assert_eq!(size, 128);
+        }
+    }
+
+    #[test]
+    fn nested_explicit_offset() {
+        let source = "struct S { a: f32, #[offset = 32] b: Vec4 }
+            #[set] struct Set { #![stage(COMPUTE)] direct: StorageBuffer<S>, nested: StorageBuffer<[S]> }";
+        let err = PlayoutModule::try_from(source).err().unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Struct `S` has fields with explicit offsets"));
+    }
+
+    #[test]
+    fn offset_overlap() {
+        let err = PlayoutModule::try_from("struct S { a: Vec4, #[offset = 8] b: f32 }")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Field `S.b` at offset 8 overlaps the previous field ending at offset 16"
+        );
+    }
+
+    #[test]
+    fn misaligned_offset() {
+        let err = PlayoutModule::try_from("struct S { a: f32, #[offset = 4] b: Vec2 }")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Field `S.b` at offset 4 must be aligned to 8 bytes under the std430 layout"
+        );
+        // Valid under std430, but std140 aligns arrays to 16 bytes.
+        let source = "struct S { a: f32, #[offset = 4] b: [f32; 2] }";
+        assert!(layout(source, BlockLayout::Std430).is_ok());
+        assert_eq!(
+            layout(source, BlockLayout::Std140).unwrap_err().message,
+            "Field `S.b` at offset 4 must be aligned to 16 bytes under the std140 layout"
+        );
+    }
+}
@@ -1,21 +1,40 @@
 #![feature(alloc_layout_extra)]
 
+mod descriptor_buffer;
+mod device_ptr;
+mod features;
+mod layout;
+mod limits;
+mod padding;
 #[cfg(feature = "parser")]
 pub mod parser;
 mod types;
 
 use std::collections::BTreeMap;
 
+pub use descriptor_buffer::*;
+pub use device_ptr::*;
+pub use features::*;
+pub use layout::*;
+pub use limits::*;
+pub use padding::*;
 pub use types::*;
 
 #[cfg(feature = "glsl")]
 mod glsl;
+#[cfg(feature = "ash")]
+mod vk;
+#[cfg(feature = "ash")]
+pub use vk::*;
 
 #[derive(Default)]
 pub struct PlayoutModule {
     pub descriptor_sets: Vec<SetLayout>,
     pub push_constants: PushConstantsLayout,
     pub data_structs: BTreeMap<String, DataStruct>,
+    pub data_enums: BTreeMap<String, DataEnum>,
+    pub type_aliases: BTreeMap<String, TypeAlias>,
+    pub samplers: BTreeMap<String, ImmutableSampler>,
 }
 
 pub use syn::Error;
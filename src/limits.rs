@@ -0,0 +1,779 @@
+use crate::{BlockLayout, DescriptorType, MatrixLayout, PlayoutModule, SetLayout, ShaderStages};
+
+macro_rules! limits {
+    ($($field:ident => $name:literal,)*) => {
+        /// Device limits that apply to pipeline layouts, from `VkPhysicalDeviceLimits` and the
+        /// properties of Vulkan 1.2, Vulkan 1.3, `VK_KHR_acceleration_structure` and
+        /// `VK_KHR_push_descriptor`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct Limits {
+            $(pub $field: u32,)*
+        }
+
+        impl Limits {
+            /// Set the limit with the Vulkan name `name`, like `maxBoundDescriptorSets`. Returns
+            /// `false` for limits that aren't tracked.
+            pub fn set(&mut self, name: &str, value: u32) -> bool {
+                match name {
+                    $($name => self.$field = value,)*
+                    _ => return false,
+                }
+                true
+            }
+        }
+    };
+}
+
+limits! {
+    max_bound_descriptor_sets => "maxBoundDescriptorSets",
+    max_push_constants_size => "maxPushConstantsSize",
+    max_per_stage_resources => "maxPerStageResources",
+    max_per_stage_descriptor_samplers => "maxPerStageDescriptorSamplers",
+    max_per_stage_descriptor_uniform_buffers => "maxPerStageDescriptorUniformBuffers",
+    max_per_stage_descriptor_storage_buffers => "maxPerStageDescriptorStorageBuffers",
+    max_per_stage_descriptor_sampled_images => "maxPerStageDescriptorSampledImages",
+    max_per_stage_descriptor_storage_images => "maxPerStageDescriptorStorageImages",
+    max_per_stage_descriptor_input_attachments => "maxPerStageDescriptorInputAttachments",
+    max_per_stage_descriptor_inline_uniform_blocks => "maxPerStageDescriptorInlineUniformBlocks",
+    max_per_stage_descriptor_acceleration_structures => "maxPerStageDescriptorAccelerationStructures",
+    max_descriptor_set_samplers => "maxDescriptorSetSamplers",
+    max_descriptor_set_uniform_buffers => "maxDescriptorSetUniformBuffers",
+    max_descriptor_set_uniform_buffers_dynamic => "maxDescriptorSetUniformBuffersDynamic",
+    max_descriptor_set_storage_buffers => "maxDescriptorSetStorageBuffers",
+    max_descriptor_set_storage_buffers_dynamic => "maxDescriptorSetStorageBuffersDynamic",
+    max_descriptor_set_sampled_images => "maxDescriptorSetSampledImages",
+    max_descriptor_set_storage_images => "maxDescriptorSetStorageImages",
+    max_descriptor_set_input_attachments => "maxDescriptorSetInputAttachments",
+    max_descriptor_set_inline_uniform_blocks => "maxDescriptorSetInlineUniformBlocks",
+    max_descriptor_set_acceleration_structures => "maxDescriptorSetAccelerationStructures",
+    max_inline_uniform_block_size => "maxInlineUniformBlockSize",
+    max_push_descriptors => "maxPushDescriptors",
+    max_per_stage_update_after_bind_resources => "maxPerStageUpdateAfterBindResources",
+    max_per_stage_descriptor_update_after_bind_samplers => "maxPerStageDescriptorUpdateAfterBindSamplers",
+    max_per_stage_descriptor_update_after_bind_uniform_buffers => "maxPerStageDescriptorUpdateAfterBindUniformBuffers",
+    max_per_stage_descriptor_update_after_bind_storage_buffers => "maxPerStageDescriptorUpdateAfterBindStorageBuffers",
+    max_per_stage_descriptor_update_after_bind_sampled_images => "maxPerStageDescriptorUpdateAfterBindSampledImages",
+    max_per_stage_descriptor_update_after_bind_storage_images => "maxPerStageDescriptorUpdateAfterBindStorageImages",
+    max_per_stage_descriptor_update_after_bind_input_attachments => "maxPerStageDescriptorUpdateAfterBindInputAttachments",
+    max_per_stage_descriptor_update_after_bind_inline_uniform_blocks => "maxPerStageDescriptorUpdateAfterBindInlineUniformBlocks",
+    max_per_stage_descriptor_update_after_bind_acceleration_structures => "maxPerStageDescriptorUpdateAfterBindAccelerationStructures",
+    max_descriptor_set_update_after_bind_samplers => "maxDescriptorSetUpdateAfterBindSamplers",
+    max_descriptor_set_update_after_bind_uniform_buffers => "maxDescriptorSetUpdateAfterBindUniformBuffers",
+    max_descriptor_set_update_after_bind_uniform_buffers_dynamic => "maxDescriptorSetUpdateAfterBindUniformBuffersDynamic",
+    max_descriptor_set_update_after_bind_storage_buffers => "maxDescriptorSetUpdateAfterBindStorageBuffers",
+    max_descriptor_set_update_after_bind_storage_buffers_dynamic => "maxDescriptorSetUpdateAfterBindStorageBuffersDynamic",
+    max_descriptor_set_update_after_bind_sampled_images => "maxDescriptorSetUpdateAfterBindSampledImages",
+    max_descriptor_set_update_after_bind_storage_images => "maxDescriptorSetUpdateAfterBindStorageImages",
+    max_descriptor_set_update_after_bind_input_attachments => "maxDescriptorSetUpdateAfterBindInputAttachments",
+    max_descriptor_set_update_after_bind_inline_uniform_blocks => "maxDescriptorSetUpdateAfterBindInlineUniformBlocks",
+    max_descriptor_set_update_after_bind_acceleration_structures => "maxDescriptorSetUpdateAfterBindAccelerationStructures",
+}
+
+impl Limits {
+    /// The minimums the Vulkan 1.3 specification requires. Update-after-bind and acceleration
+    /// structure limits are those of devices supporting the corresponding features.
+    pub const VULKAN_1_3: Limits = Limits {
+        max_bound_descriptor_sets: 4,
+        max_push_constants_size: 128,
+        max_per_stage_resources: 128,
+        max_per_stage_descriptor_samplers: 16,
+        max_per_stage_descriptor_uniform_buffers: 12,
+        max_per_stage_descriptor_storage_buffers: 4,
+        max_per_stage_descriptor_sampled_images: 16,
+        max_per_stage_descriptor_storage_images: 4,
+        max_per_stage_descriptor_input_attachments: 4,
+        max_per_stage_descriptor_inline_uniform_blocks: 4,
+        max_per_stage_descriptor_acceleration_structures: 16,
+        max_descriptor_set_samplers: 96,
+        max_descriptor_set_uniform_buffers: 72,
+        max_descriptor_set_uniform_buffers_dynamic: 8,
+        max_descriptor_set_storage_buffers: 24,
+        max_descriptor_set_storage_buffers_dynamic: 4,
+        max_descriptor_set_sampled_images: 96,
+        max_descriptor_set_storage_images: 24,
+        max_descriptor_set_input_attachments: 4,
+        max_descriptor_set_inline_uniform_blocks: 4,
+        max_descriptor_set_acceleration_structures: 16,
+        max_inline_uniform_block_size: 256,
+        max_push_descriptors: 32,
+        max_per_stage_update_after_bind_resources: 500000,
+        max_per_stage_descriptor_update_after_bind_samplers: 500000,
+        max_per_stage_descriptor_update_after_bind_uniform_buffers: 12,
+        max_per_stage_descriptor_update_after_bind_storage_buffers: 500000,
+        max_per_stage_descriptor_update_after_bind_sampled_images: 500000,
+        max_per_stage_descriptor_update_after_bind_storage_images: 500000,
+        max_per_stage_descriptor_update_after_bind_input_attachments: 4,
+        max_per_stage_descriptor_update_after_bind_inline_uniform_blocks: 4,
+        max_per_stage_descriptor_update_after_bind_acceleration_structures: 500000,
+        max_descriptor_set_update_after_bind_samplers: 500000,
+        max_descriptor_set_update_after_bind_uniform_buffers: 72,
+        max_descriptor_set_update_after_bind_uniform_buffers_dynamic: 8,
+        max_descriptor_set_update_after_bind_storage_buffers: 500000,
+        max_descriptor_set_update_after_bind_storage_buffers_dynamic: 4,
+        max_descriptor_set_update_after_bind_sampled_images: 500000,
+        max_descriptor_set_update_after_bind_storage_images: 500000,
+        max_descriptor_set_update_after_bind_input_attachments: 4,
+        max_descriptor_set_update_after_bind_inline_uniform_blocks: 4,
+        max_descriptor_set_update_after_bind_acceleration_structures: 500000,
+    };
+
+    /// The minimums of the Vulkan Roadmap 2022 profile, `VP_KHR_roadmap_2022`.
+    pub const ROADMAP_2022: Limits = Limits {
+        max_bound_descriptor_sets: 7,
+        max_per_stage_resources: 200,
+        max_per_stage_descriptor_samplers: 64,
+        max_per_stage_descriptor_uniform_buffers: 15,
+        max_per_stage_descriptor_storage_buffers: 30,
+        max_per_stage_descriptor_sampled_images: 200,
+        max_per_stage_descriptor_storage_images: 16,
+        max_descriptor_set_samplers: 576,
+        max_descriptor_set_uniform_buffers: 90,
+        max_descriptor_set_storage_buffers: 96,
+        max_descriptor_set_sampled_images: 1800,
+        max_descriptor_set_storage_images: 144,
+        ..Limits::VULKAN_1_3
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::VULKAN_1_3
+    }
+}
+
+/// A limit exceeded by a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitViolation {
+    /// The Vulkan name of the limit, like `maxPerStageDescriptorStorageImages`.
+    pub limit: &'static str,
+    pub max: u32,
+    pub required: u64,
+    /// What needs more than the limit, like `FRAGMENT stage` or `set Materials`.
+    pub location: String,
+}
+
+impl std::fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} needs {}, more than {} = {}",
+            self.location, self.required, self.limit, self.max
+        )
+    }
+}
+
+/// The descriptor counts limits are expressed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DescriptorClass {
+    Sampler,
+    UniformBuffer,
+    UniformBufferDynamic,
+    StorageBuffer,
+    StorageBufferDynamic,
+    SampledImage,
+    StorageImage,
+    InputAttachment,
+    InlineUniformBlock,
+    AccelerationStructure,
+}
+
+const CLASSES: [DescriptorClass; 10] = [
+    DescriptorClass::Sampler,
+    DescriptorClass::UniformBuffer,
+    DescriptorClass::UniformBufferDynamic,
+    DescriptorClass::StorageBuffer,
+    DescriptorClass::StorageBufferDynamic,
+    DescriptorClass::SampledImage,
+    DescriptorClass::StorageImage,
+    DescriptorClass::InputAttachment,
+    DescriptorClass::InlineUniformBlock,
+    DescriptorClass::AccelerationStructure,
+];
+
+/// Descriptor counts indexed by `DescriptorClass`.
+type Counts = [u64; CLASSES.len()];
+
+impl DescriptorClass {
+    /// The classes a descriptor counts against. Mutable descriptors count against the classes
+    /// of all of their members.
+    fn of(descriptor_type: &DescriptorType) -> Vec<DescriptorClass> {
+        use DescriptorClass::*;
+        match descriptor_type {
+            DescriptorType::Sampler { .. } => vec![Sampler],
+            DescriptorType::CombinedImageSampler { .. } => vec![Sampler, SampledImage],
+            DescriptorType::SampledImage { .. } | DescriptorType::UniformTexelBuffer { .. } => {
+                vec![SampledImage]
+            }
+            DescriptorType::StorageImage { .. } | DescriptorType::StorageTexelBuffer { .. } => {
+                vec![StorageImage]
+            }
+            DescriptorType::UniformBuffer { .. } => vec![UniformBuffer],
+            DescriptorType::UniformBufferDynamic { .. } => {
+                vec![UniformBuffer, UniformBufferDynamic]
+            }
+            DescriptorType::StorageBuffer { .. } => vec![StorageBuffer],
+            DescriptorType::StorageBufferDynamic { .. } => {
+                vec![StorageBuffer, StorageBufferDynamic]
+            }
+            DescriptorType::InputAttachment { .. } => vec![InputAttachment],
+            DescriptorType::InlineUniformBlock { .. } => vec![InlineUniformBlock],
+            DescriptorType::AccelerationStructure => vec![AccelerationStructure],
+            DescriptorType::Mutable { types } => {
+                let mut classes: Vec<DescriptorClass> = Vec::new();
+                for class in types.iter().flat_map(DescriptorClass::of) {
+                    if !classes.contains(&class) {
+                        classes.push(class);
+                    }
+                }
+                classes
+            }
+        }
+    }
+
+    /// Counted by `maxPerStageResources`.
+    fn is_resource(self) -> bool {
+        !matches!(
+            self,
+            DescriptorClass::Sampler
+                | DescriptorClass::UniformBufferDynamic
+                | DescriptorClass::StorageBufferDynamic
+                | DescriptorClass::InlineUniformBlock
+                | DescriptorClass::AccelerationStructure
+        )
+    }
+
+    /// The per-stage limit on the class, if there is one.
+    fn per_stage_limit(
+        self,
+        limits: &Limits,
+        update_after_bind: bool,
+    ) -> Option<(&'static str, u32)> {
+        use DescriptorClass::*;
+        Some(match (self, update_after_bind) {
+            (Sampler, false) => (
+                "maxPerStageDescriptorSamplers",
+                limits.max_per_stage_descriptor_samplers,
+            ),
+            (UniformBuffer, false) => (
+                "maxPerStageDescriptorUniformBuffers",
+                limits.max_per_stage_descriptor_uniform_buffers,
+            ),
+            (StorageBuffer, false) => (
+                "maxPerStageDescriptorStorageBuffers",
+                limits.max_per_stage_descriptor_storage_buffers,
+            ),
+            (SampledImage, false) => (
+                "maxPerStageDescriptorSampledImages",
+                limits.max_per_stage_descriptor_sampled_images,
+            ),
+            (StorageImage, false) => (
+                "maxPerStageDescriptorStorageImages",
+                limits.max_per_stage_descriptor_storage_images,
+            ),
+            (InputAttachment, false) => (
+                "maxPerStageDescriptorInputAttachments",
+                limits.max_per_stage_descriptor_input_attachments,
+            ),
+            (InlineUniformBlock, false) => (
+                "maxPerStageDescriptorInlineUniformBlocks",
+                limits.max_per_stage_descriptor_inline_uniform_blocks,
+            ),
+            (AccelerationStructure, false) => (
+                "maxPerStageDescriptorAccelerationStructures",
+                limits.max_per_stage_descriptor_acceleration_structures,
+            ),
+            (Sampler, true) => (
+                "maxPerStageDescriptorUpdateAfterBindSamplers",
+                limits.max_per_stage_descriptor_update_after_bind_samplers,
+            ),
+            (UniformBuffer, true) => (
+                "maxPerStageDescriptorUpdateAfterBindUniformBuffers",
+                limits.max_per_stage_descriptor_update_after_bind_uniform_buffers,
+            ),
+            (StorageBuffer, true) => (
+                "maxPerStageDescriptorUpdateAfterBindStorageBuffers",
+                limits.max_per_stage_descriptor_update_after_bind_storage_buffers,
+            ),
+            (SampledImage, true) => (
+                "maxPerStageDescriptorUpdateAfterBindSampledImages",
+                limits.max_per_stage_descriptor_update_after_bind_sampled_images,
+            ),
+            (StorageImage, true) => (
+                "maxPerStageDescriptorUpdateAfterBindStorageImages",
+                limits.max_per_stage_descriptor_update_after_bind_storage_images,
+            ),
+            (InputAttachment, true) => (
+                "maxPerStageDescriptorUpdateAfterBindInputAttachments",
+                limits.max_per_stage_descriptor_update_after_bind_input_attachments,
+            ),
+            (InlineUniformBlock, true) => (
+                "maxPerStageDescriptorUpdateAfterBindInlineUniformBlocks",
+                limits.max_per_stage_descriptor_update_after_bind_inline_uniform_blocks,
+            ),
+            (AccelerationStructure, true) => (
+                "maxPerStageDescriptorUpdateAfterBindAccelerationStructures",
+                limits.max_per_stage_descriptor_update_after_bind_acceleration_structures,
+            ),
+            (UniformBufferDynamic | StorageBufferDynamic, _) => return None,
+        })
+    }
+
+    /// The limit on the class across all sets of a pipeline layout.
+    fn pipeline_layout_limit(
+        self,
+        limits: &Limits,
+        update_after_bind: bool,
+    ) -> (&'static str, u32) {
+        use DescriptorClass::*;
+        match (self, update_after_bind) {
+            (Sampler, false) => (
+                "maxDescriptorSetSamplers",
+                limits.max_descriptor_set_samplers,
+            ),
+            (UniformBuffer, false) => (
+                "maxDescriptorSetUniformBuffers",
+                limits.max_descriptor_set_uniform_buffers,
+            ),
+            (UniformBufferDynamic, false) => (
+                "maxDescriptorSetUniformBuffersDynamic",
+                limits.max_descriptor_set_uniform_buffers_dynamic,
+            ),
+            (StorageBuffer, false) => (
+                "maxDescriptorSetStorageBuffers",
+                limits.max_descriptor_set_storage_buffers,
+            ),
+            (StorageBufferDynamic, false) => (
+                "maxDescriptorSetStorageBuffersDynamic",
+                limits.max_descriptor_set_storage_buffers_dynamic,
+            ),
+            (SampledImage, false) => (
+                "maxDescriptorSetSampledImages",
+                limits.max_descriptor_set_sampled_images,
+            ),
+            (StorageImage, false) => (
+                "maxDescriptorSetStorageImages",
+                limits.max_descriptor_set_storage_images,
+            ),
+            (InputAttachment, false) => (
+                "maxDescriptorSetInputAttachments",
+                limits.max_descriptor_set_input_attachments,
+            ),
+            (InlineUniformBlock, false) => (
+                "maxDescriptorSetInlineUniformBlocks",
+                limits.max_descriptor_set_inline_uniform_blocks,
+            ),
+            (AccelerationStructure, false) => (
+                "maxDescriptorSetAccelerationStructures",
+                limits.max_descriptor_set_acceleration_structures,
+            ),
+            (Sampler, true) => (
+                "maxDescriptorSetUpdateAfterBindSamplers",
+                limits.max_descriptor_set_update_after_bind_samplers,
+            ),
+            (UniformBuffer, true) => (
+                "maxDescriptorSetUpdateAfterBindUniformBuffers",
+                limits.max_descriptor_set_update_after_bind_uniform_buffers,
+            ),
+            (UniformBufferDynamic, true) => (
+                "maxDescriptorSetUpdateAfterBindUniformBuffersDynamic",
+                limits.max_descriptor_set_update_after_bind_uniform_buffers_dynamic,
+            ),
+            (StorageBuffer, true) => (
+                "maxDescriptorSetUpdateAfterBindStorageBuffers",
+                limits.max_descriptor_set_update_after_bind_storage_buffers,
+            ),
+            (StorageBufferDynamic, true) => (
+                "maxDescriptorSetUpdateAfterBindStorageBuffersDynamic",
+                limits.max_descriptor_set_update_after_bind_storage_buffers_dynamic,
+            ),
+            (SampledImage, true) => (
+                "maxDescriptorSetUpdateAfterBindSampledImages",
+                limits.max_descriptor_set_update_after_bind_sampled_images,
+            ),
+            (StorageImage, true) => (
+                "maxDescriptorSetUpdateAfterBindStorageImages",
+                limits.max_descriptor_set_update_after_bind_storage_images,
+            ),
+            (InputAttachment, true) => (
+                "maxDescriptorSetUpdateAfterBindInputAttachments",
+                limits.max_descriptor_set_update_after_bind_input_attachments,
+            ),
+            (InlineUniformBlock, true) => (
+                "maxDescriptorSetUpdateAfterBindInlineUniformBlocks",
+                limits.max_descriptor_set_update_after_bind_inline_uniform_blocks,
+            ),
+            (AccelerationStructure, true) => (
+                "maxDescriptorSetUpdateAfterBindAccelerationStructures",
+                limits.max_descriptor_set_update_after_bind_acceleration_structures,
+            ),
+        }
+    }
+}
+
+impl SetLayout {
+    /// Descriptor counts of the bindings visible to `stages`.
+    fn descriptor_counts(&self, stages: ShaderStages) -> Counts {
+        let mut counts = [0; CLASSES.len()];
+        for binding in self.bindings.iter() {
+            if !binding.stages.intersects(stages) {
+                continue;
+            }
+            for class in DescriptorClass::of(&binding.descriptor_type) {
+                counts[class as usize] += binding.descriptor_count as u64;
+            }
+        }
+        counts
+    }
+}
+
+impl PlayoutModule {
+    /// Check the pipeline layout made of every set and the push constants of the module against
+    /// `limits`. Per-stage limits sum the bindings of all sets visible to each stage, and
+    /// `maxDescriptorSet*` limits sum all sets. When any set is update-after-bind, the
+    /// update-after-bind limits apply to all sets and the other limits to the remaining sets.
+    pub fn check_limits(&self, limits: &Limits) -> Vec<LimitViolation> {
+        let mut violations = Vec::new();
+        let mut check = |limit: &'static str, max: u32, required: u64, location: String| {
+            if required > max as u64 {
+                violations.push(LimitViolation {
+                    limit,
+                    max,
+                    required,
+                    location,
+                });
+            }
+        };
+
+        let set_count = self
+            .descriptor_sets
+            .iter()
+            .map(|set| set.set as u64 + 1)
+            .max()
+            .unwrap_or(0);
+        check(
+            "maxBoundDescriptorSets",
+            limits.max_bound_descriptor_sets,
+            set_count,
+            "pipeline layout".to_string(),
+        );
+        if let Ok(layout) = self.push_constants.layout_in(self, BlockLayout::default()) {
+            let end = self
+                .push_constants
+                .fields
+                .iter()
+                .zip(layout.offsets.iter())
+                .map(|(field, offset)| {
+                    let major = field.field.matrix_layout.unwrap_or_default();
+                    field
+                        .field
+                        .ty
+                        .layout_in(self, BlockLayout::default(), major)
+                        .map_or(0, |layout| offset + layout.size())
+                })
+                .max()
+                .unwrap_or(0);
+            check(
+                "maxPushConstantsSize",
+                limits.max_push_constants_size,
+                end.next_multiple_of(4) as u64,
+                "push constants".to_string(),
+            );
+        }
+        for set in self.descriptor_sets.iter() {
+            for binding in set.bindings.iter() {
+                if let DescriptorType::InlineUniformBlock { ty } = &binding.descriptor_type {
+                    if let Ok(layout) =
+                        ty.layout_in(self, binding.block_layout(), MatrixLayout::default())
+                    {
+                        check(
+                            "maxInlineUniformBlockSize",
+                            limits.max_inline_uniform_block_size,
+                            layout.size() as u64,
+                            format!("binding {}.{}", set.name, binding.ident),
+                        );
+                    }
+                }
+            }
+            if set.push_descriptor {
+                check(
+                    "maxPushDescriptors",
+                    limits.max_push_descriptors,
+                    set.bindings
+                        .iter()
+                        .map(|binding| binding.descriptor_count as u64)
+                        .sum(),
+                    format!("set {}", set.name),
+                );
+            }
+        }
+
+        let update_after_bind = self
+            .descriptor_sets
+            .iter()
+            .any(SetLayout::update_after_bind);
+        let sum = |stages: ShaderStages, all_sets: bool| {
+            let mut counts = [0; CLASSES.len()];
+            for set in self.descriptor_sets.iter() {
+                if all_sets || !set.update_after_bind() {
+                    for (total, count) in counts.iter_mut().zip(set.descriptor_counts(stages)) {
+                        *total += count;
+                    }
+                }
+            }
+            counts
+        };
+        let mut passes = vec![false];
+        if update_after_bind {
+            passes.push(true);
+        }
+        for update_after_bind in passes {
+            let (resources_limit, max_resources) = if update_after_bind {
+                (
+                    "maxPerStageUpdateAfterBindResources",
+                    limits.max_per_stage_update_after_bind_resources,
+                )
+            } else {
+                ("maxPerStageResources", limits.max_per_stage_resources)
+            };
+            let stages = self
+                .descriptor_sets
+                .iter()
+                .flat_map(|set| set.bindings.iter())
+                .fold(ShaderStages::empty(), |stages, binding| {
+                    stages | binding.stages
+                });
+            for (name, stage) in stages.iter_names() {
+                let counts = sum(stage, update_after_bind);
+                let location = format!("{} stage", name);
+                for class in CLASSES {
+                    if let Some((limit, max)) = class.per_stage_limit(limits, update_after_bind) {
+                        check(limit, max, counts[class as usize], location.clone());
+                    }
+                }
+                let resources = CLASSES
+                    .iter()
+                    .filter(|class| class.is_resource())
+                    .map(|class| counts[*class as usize])
+                    .sum();
+                check(resources_limit, max_resources, resources, location);
+            }
+            let counts = sum(ShaderStages::all(), update_after_bind);
+            for class in CLASSES {
+                let (limit, max) = class.pipeline_layout_limit(limits, update_after_bind);
+                check(
+                    limit,
+                    max,
+                    counts[class as usize],
+                    "pipeline layout".to_string(),
+                );
+            }
+        }
+        violations
+    }
+}
+
+/// Error loading limits from a Vulkan Profiles file.
+#[cfg(feature = "profiles")]
+#[derive(Debug)]
+pub struct ProfileError {
+    pub message: String,
+}
+
+#[cfg(feature = "profiles")]
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "profiles")]
+impl std::error::Error for ProfileError {}
+
+#[cfg(feature = "profiles")]
+impl Limits {
+    /// Limits of `profile` in a Vulkan Profiles JSON file, such as `VP_KHR_roadmap_2022` in
+    /// `VP_KHR_roadmap.json`. Limits the profile doesn't list keep their value in `base`. When
+    /// the profile lists alternative capabilities, the first one is used.
+    pub fn from_profiles_json(
+        json: &str,
+        profile: &str,
+        base: Limits,
+    ) -> Result<Limits, ProfileError> {
+        let error = |message: String| ProfileError { message };
+        let root: serde_json::Value =
+            serde_json::from_str(json).map_err(|err| error(err.to_string()))?;
+        let capabilities = root["profiles"][profile]["capabilities"]
+            .as_array()
+            .ok_or_else(|| error(format!("No profile `{}`", profile)))?;
+        let mut limits = base;
+        for capability in capabilities {
+            let name = match capability {
+                serde_json::Value::Array(alternatives) => alternatives.first(),
+                name => Some(name),
+            }
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| error(format!("Invalid capability in profile `{}`", profile)))?;
+            let properties = &root["capabilities"][name]["properties"];
+            if root["capabilities"][name].is_null() {
+                return Err(error(format!("No capability `{}`", name)));
+            }
+            limits.set_from_json(properties);
+        }
+        Ok(limits)
+    }
+
+    /// Properties are grouped by Vulkan struct, with limits nested in `VkPhysicalDeviceProperties`.
+    fn set_from_json(&mut self, properties: &serde_json::Value) {
+        let Some(properties) = properties.as_object() else {
+            return;
+        };
+        for (name, value) in properties {
+            match value.as_u64() {
+                Some(value) => {
+                    self.set(name, value.min(u32::MAX as u64) as u32);
+                }
+                None => self.set_from_json(value),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod tests {
+    use super::*;
+
+    fn check(source: &str, limits: &Limits) -> Vec<(&'static str, u64, String)> {
+        let module = PlayoutModule::try_from(source).unwrap();
+        module
+            .check_limits(limits)
+            .into_iter()
+            .map(|violation| (violation.limit, violation.required, violation.location))
+            .collect()
+    }
+
+    #[test]
+    fn per_stage_sum() {
+        // The fragment stage sees the storage images of both sets.
+        let source = "
+            #[set = 0] struct A { #![stage(FRAGMENT)] a: [StorageImage<RGBA8_UNorm>; 3] }
+            #[set = 1] struct B { #![stage(FRAGMENT | COMPUTE)] b: [StorageImage<RGBA8_UNorm>; 2] }";
+        assert_eq!(
+            check(source, &Limits::VULKAN_1_3),
+            [(
+                "maxPerStageDescriptorStorageImages",
+                5,
+                "FRAGMENT stage".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn pipeline_layout_sum() {
+        let source = "
+            #[set = 0] struct A { #![stage(VERTEX)] a: [StorageImage<RGBA8_UNorm>; 3] }
+            #[set = 1] struct B { #![stage(FRAGMENT)] b: [StorageImage<RGBA8_UNorm>; 2] }";
+        let limits = Limits {
+            max_descriptor_set_storage_images: 4,
+            ..Limits::VULKAN_1_3
+        };
+        assert_eq!(
+            check(source, &limits),
+            [(
+                "maxDescriptorSetStorageImages",
+                5,
+                "pipeline layout".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn update_after_bind() {
+        // The update-after-bind set only counts against the update-after-bind limits.
+        let source = "
+            #[set = 0] struct A { #![stage(FRAGMENT)] a: [StorageImage<RGBA8_UNorm>; 3] }
+            #[set = 1] struct B {
+                #![stage(FRAGMENT)]
+                #[update_after_bind]
+                b: [StorageImage<RGBA8_UNorm>; 2],
+            }";
+        assert!(check(source, &Limits::VULKAN_1_3).is_empty());
+        let limits = Limits {
+            max_per_stage_descriptor_update_after_bind_storage_images: 4,
+            ..Limits::VULKAN_1_3
+        };
+        assert_eq!(
+            check(source, &limits),
+            [(
+                "maxPerStageDescriptorUpdateAfterBindStorageImages",
+                5,
+                "FRAGMENT stage".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn inline_uniform_block_size() {
+        let source = "
+            struct S { a: [Vec4; 20] }
+            #[set] struct Set { #![stage(COMPUTE)] block: InlineUniformBlock<S> }";
+        assert_eq!(
+            check(source, &Limits::VULKAN_1_3),
+            [(
+                "maxInlineUniformBlockSize",
+                320,
+                "binding Set.block".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn bound_descriptor_sets() {
+        let source = "#[set = 4] struct Set { #![stage(COMPUTE)] a: StorageBuffer<[u32]> }";
+        assert_eq!(
+            check(source, &Limits::VULKAN_1_3),
+            [("maxBoundDescriptorSets", 5, "pipeline layout".to_string())]
+        );
+        assert!(check(source, &Limits::ROADMAP_2022).is_empty());
+    }
+
+    #[cfg(feature = "profiles")]
+    #[test]
+    fn profiles_json() {
+        let json = r#"{
+            "capabilities": {
+                "base": {
+                    "properties": {
+                        "VkPhysicalDeviceProperties": {
+                            "limits": { "maxBoundDescriptorSets": 8, "maxPerStageResources": 300 }
+                        }
+                    }
+                },
+                "first": {
+                    "properties": {
+                        "VkPhysicalDeviceVulkan13Properties": { "maxInlineUniformBlockSize": 512 }
+                    }
+                },
+                "second": {
+                    "properties": {
+                        "VkPhysicalDeviceVulkan13Properties": { "maxInlineUniformBlockSize": 1024 }
+                    }
+                }
+            },
+            "profiles": {
+                "VP_TEST": { "capabilities": ["base", ["first", "second"]] }
+            }
+        }"#;
+        let limits = Limits::from_profiles_json(json, "VP_TEST", Limits::VULKAN_1_3).unwrap();
+        assert_eq!(
+            limits,
+            Limits {
+                max_bound_descriptor_sets: 8,
+                max_per_stage_resources: 300,
+                max_inline_uniform_block_size: 512,
+                ..Limits::VULKAN_1_3
+            }
+        );
+        let err = Limits::from_profiles_json(json, "VP_MISSING", Limits::VULKAN_1_3).unwrap_err();
+        assert_eq!(err.message, "No profile `VP_MISSING`");
+    }
+}
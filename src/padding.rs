@@ -0,0 +1,264 @@
+use crate::{
+    layout::layout_fields, BlockLayout, DataStruct, Field, LayoutError, MatrixLayout,
+    PlayoutModule, PrimitiveType, Type,
+};
+
+/// Bytes left unused between two fields, or at the end of a struct.
+#[derive(Debug, Clone)]
+pub struct PaddingGap {
+    pub offset: usize,
+    pub size: usize,
+    /// The field preceding the gap, or `None` for a gap at the start of the struct.
+    pub after: Option<String>,
+}
+
+/// How much space a data struct wastes on padding under a block layout.
+#[derive(Debug, Clone)]
+pub struct PaddingReport {
+    pub ident: String,
+    pub block: BlockLayout,
+    pub size: usize,
+    pub padding: usize,
+    pub gaps: Vec<PaddingGap>,
+    /// A field ordering with less padding, and the struct size with that ordering.
+    pub suggestion: Option<(Vec<String>, usize)>,
+    pub warnings: Vec<String>,
+}
+
+impl PlayoutModule {
+    pub fn padding_reports(&self, block: BlockLayout) -> Result<Vec<PaddingReport>, LayoutError> {
+        self.data_structs
+            .values()
+            .map(|data_struct| data_struct.padding_report(self, block))
+            .collect()
+    }
+}
+
+impl DataStruct {
+    pub fn padding_report(
+        &self,
+        module: &PlayoutModule,
+        block: BlockLayout,
+    ) -> Result<PaddingReport, LayoutError> {
+        let layout = self.layout_in(module, block, MatrixLayout::default())?;
+        let mut gaps = Vec::new();
+        let mut warnings = Vec::new();
+        let mut end = 0;
+        let mut previous: Option<&Field> = None;
+        for (field, &offset) in self.fields.iter().zip(layout.offsets.iter()) {
+            if offset > end {
+                gaps.push(PaddingGap {
+                    offset: end,
+                    size: offset - end,
+                    after: previous.and_then(|field| field.ident.clone()),
+                });
+                if let Some(component) = previous.and_then(|field| vec3_component(module, field)) {
+                    warnings.push(format!(
+                        "`{}.{}` is a Vec3 followed by `{}` which can't fill its trailing {} bytes",
+                        self.ident,
+                        field_name(previous.unwrap()),
+                        field_name(field),
+                        component
+                    ));
+                }
+            }
+            end = offset + field_size(module, field, block)?;
+            previous = Some(field);
+        }
+        // Structs ending with a runtime sized array have no trailing padding of their own.
+        let unsized_struct =
+            previous.is_some_and(|field| matches!(field.ty.resolve(module), Type::Slice { .. }));
+        if !unsized_struct && layout.layout.size() > end {
+            gaps.push(PaddingGap {
+                offset: end,
+                size: layout.layout.size() - end,
+                after: previous.and_then(|field| field.ident.clone()),
+            });
+        }
+        let padding = gaps.iter().map(|gap| gap.size).sum();
+
+        // Explicitly placed fields can't be moved around.
+        let suggestion = if self.fields.iter().any(|field| field.offset.is_some()) {
+            None
+        } else {
+            let order = self.compact_order(module, block)?;
+            let size = layout_fields(
+                &self.ident,
+                order.iter().copied(),
+                self.align,
+                module,
+                block,
+                MatrixLayout::default(),
+            )?
+            .layout
+            .size();
+            if size < layout.layout.size() {
+                Some((order.into_iter().map(field_name).collect(), size))
+            } else {
+                None
+            }
+        };
+        Ok(PaddingReport {
+            ident: self.ident.clone(),
+            block,
+            size: layout.layout.size(),
+            padding,
+            gaps,
+            suggestion,
+            warnings,
+        })
+    }
+
+    /// Greedily pick the field that needs the least padding at the current offset, preferring
+    /// larger alignments. Runtime sized arrays stay last.
+    fn compact_order(
+        &self,
+        module: &PlayoutModule,
+        block: BlockLayout,
+    ) -> Result<Vec<&Field>, LayoutError> {
+        let mut remaining = Vec::new();
+        for field in self.fields.iter() {
+            let major = field.matrix_layout.unwrap_or_default();
+            let layout = field.ty.layout_in(module, block, major)?;
+            let align = layout.align().max(field.align.unwrap_or(1));
+            remaining.push((field, layout.size(), align));
+        }
+        let tail = match remaining.last() {
+            Some((field, ..)) if matches!(field.ty.resolve(module), Type::Slice { .. }) => {
+                remaining.pop()
+            }
+            _ => None,
+        };
+        let mut order = Vec::new();
+        let mut end: usize = 0;
+        while !remaining.is_empty() {
+            let (index, _) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (_, size, align))| {
+                    let padding = end.next_multiple_of(*align) - end;
+                    (padding, std::cmp::Reverse(*align), std::cmp::Reverse(*size))
+                })
+                .unwrap();
+            let (field, size, align) = remaining.remove(index);
+            end = end.next_multiple_of(align) + size;
+            order.push(field);
+        }
+        order.extend(tail.map(|(field, ..)| field));
+        Ok(order)
+    }
+}
+
+impl std::fmt::Display for PaddingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} ({}): {} bytes, {} bytes of padding",
+            self.ident,
+            self.block.name(),
+            self.size,
+            self.padding
+        )?;
+        for gap in self.gaps.iter() {
+            match &gap.after {
+                Some(after) => writeln!(
+                    f,
+                    "    {} bytes at offset {} after `{}`",
+                    gap.size, gap.offset, after
+                )?,
+                None => writeln!(f, "    {} bytes at offset {}", gap.size, gap.offset)?,
+            }
+        }
+        if let Some((order, size)) = &self.suggestion {
+            writeln!(
+                f,
+                "    reordering to {} would reduce the size to {} bytes",
+                order.join(", "),
+                size
+            )?;
+        }
+        for warning in self.warnings.iter() {
+            writeln!(f, "    warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+fn field_name(field: &Field) -> String {
+    field.ident.clone().unwrap_or_else(|| "_".to_string())
+}
+
+fn field_size(
+    module: &PlayoutModule,
+    field: &Field,
+    block: BlockLayout,
+) -> Result<usize, LayoutError> {
+    let major = field.matrix_layout.unwrap_or_default();
+    Ok(field.ty.layout_in(module, block, major)?.size())
+}
+
+/// Component size of a three-component vector field.
+fn vec3_component(module: &PlayoutModule, field: &Field) -> Option<usize> {
+    match field.ty.resolve(module) {
+        Type::Primitive(PrimitiveType::Vec { ty, length: 3 }) => Some(ty.layout().size()),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod tests {
+    use super::*;
+
+    fn report(source: &str, block: BlockLayout) -> PaddingReport {
+        let module = PlayoutModule::try_from(source).unwrap();
+        module.data_structs["S"]
+            .padding_report(&module, block)
+            .unwrap()
+    }
+
+    fn gaps(report: &PaddingReport) -> Vec<(usize, usize, Option<&str>)> {
+        report
+            .gaps
+            .iter()
+            .map(|gap| (gap.offset, gap.size, gap.after.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn std140_vec3() {
+        let report = report("struct S { a: Vec3, b: Vec3 }", BlockLayout::Std140);
+        assert_eq!(report.size, 32);
+        assert_eq!(report.padding, 8);
+        assert_eq!(gaps(&report), [(12, 4, Some("a")), (28, 4, Some("b"))]);
+        assert_eq!(
+            report.warnings,
+            ["`S.a` is a Vec3 followed by `b` which can't fill its trailing 4 bytes"]
+        );
+        assert!(report.suggestion.is_none());
+    }
+
+    #[test]
+    fn reorder() {
+        let report = report("struct S { a: u32, b: u64, c: u32 }", BlockLayout::Std430);
+        assert_eq!(report.size, 24);
+        assert_eq!(gaps(&report), [(4, 4, Some("a")), (20, 4, Some("c"))]);
+        let (order, size) = report.suggestion.unwrap();
+        assert_eq!(order, ["b", "a", "c"]);
+        assert_eq!(size, 16);
+    }
+
+    #[test]
+    fn runtime_array() {
+        // The runtime sized array stays last and leaves no trailing padding, also behind an alias.
+        for source in [
+            "struct S { a: u32, b: u64, c: u32, d: [u32] }",
+            "type Tail = [u32]; struct S { a: u32, b: u64, c: u32, d: Tail }",
+        ] {
+            let report = report(source, BlockLayout::Std430);
+            assert_eq!(gaps(&report), [(4, 4, Some("a"))]);
+            let (order, size) = report.suggestion.unwrap();
+            assert_eq!(order, ["b", "a", "c", "d"]);
+            assert_eq!(size, 16);
+        }
+    }
+}
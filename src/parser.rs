@@ -1,11 +1,17 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use inflector::Inflector;
 use syn::{
     ext::IdentExt,
     parse::{Parse, ParseStream},
 };
 
 use crate::{
-    Binding, DataStruct, DescriptorType, Field, ImageFormat, PlayoutModule, PrimitiveType,
-    PrimitiveTypeSingle, PushConstantField, PushConstantsLayout, SetLayout, ShaderStages, Type,
+    Binding, BindingFlags, BitfieldMember, BlockLayout, BorderColor, CompareOp, DataEnum,
+    DataStruct, DescriptorAlias, DescriptorType, EnumVariant, Field, Filter, ImageComponentType,
+    ImageDimension, ImageFormat, ImmutableSampler, LayoutError, MatrixLayout, MemoryQualifiers,
+    PlayoutModule, PrimitiveType, PrimitiveTypeSingle, PushConstantField, PushConstantsLayout,
+    SamplerAddressMode, SamplerMipmapMode, SetLayout, ShaderStages, Type, TypeAlias,
 };
 
 impl Parse for DescriptorType {
@@ -13,14 +19,154 @@ impl Parse for DescriptorType {
         let ty = input.parse::<syn::Ident>()?;
         let ty = match ty.to_string().as_str() {
             "StorageImage" => {
+                let args = ImageArgs::parse(input)?;
+                args.reject_shadow()?;
+                let component = match (args.format, args.component, args.unknown_format) {
+                    (Some((format, span)), None, None) => {
+                        check_storage_format(format, span)?;
+                        format.data_mode().component_type()
+                    }
+                    (None, Some((component, _)), None) => component,
+                    (None, None, Some(_)) => ImageComponentType::Float,
+                    (None, None, None) => {
+                        return Err(syn::Error::new(
+                            ty.span(),
+                            "Storage images require a format, `Unknown` or a component type",
+                        ));
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            ty.span(),
+                            "Storage images take either a format, `Unknown` or a component type",
+                        ));
+                    }
+                };
+                Self::StorageImage {
+                    format: args.format.map(|(format, _)| format),
+                    component,
+                    dimension: args.dimension(),
+                }
+            }
+            "Sampler" => {
+                let args = ImageArgs::parse(input)?;
+                if let Some((_, span)) = args.dimension {
+                    return Err(syn::Error::new(span, "Samplers have no dimension"));
+                }
+                args.reject_format()?;
+                args.reject_component()?;
+                Self::Sampler {
+                    shadow: args.shadow.is_some(),
+                }
+            }
+            "SampledImage" => {
+                let args = ImageArgs::parse(input)?;
+                args.reject_format()?;
+                if let Some(span) = args.shadow {
+                    return Err(syn::Error::new(
+                        span,
+                        "Use `Sampler<Shadow>` or `CombinedImageSampler<Shadow>` for depth comparison",
+                    ));
+                }
+                Self::SampledImage {
+                    dimension: args.dimension(),
+                    component: args.component(),
+                }
+            }
+            "CombinedImageSampler" => {
+                let args = ImageArgs::parse(input)?;
+                args.reject_format()?;
+                if let (Some(span), false) = (args.shadow, args.dimension().supports_shadow()) {
+                    return Err(syn::Error::new(
+                        span,
+                        "Depth comparison is not supported on 3D and multisampled images",
+                    ));
+                }
+                if let (
+                    Some(_),
+                    Some((ImageComponentType::SInt | ImageComponentType::UInt, span)),
+                ) = (args.shadow, args.component)
+                {
+                    return Err(syn::Error::new(
+                        span,
+                        "Depth comparison is only supported on floating point images",
+                    ));
+                }
+                Self::CombinedImageSampler {
+                    dimension: args.dimension(),
+                    component: args.component(),
+                    shadow: args.shadow.is_some(),
+                }
+            }
+            "AccelerationStructure" => Self::AccelerationStructure,
+            "UniformTexelBuffer" => {
                 let _left: syn::Token![<] = input.parse()?;
+                let span = input.span();
                 let format: ImageFormat = input.parse()?;
+                if format.is_64_bit() {
+                    return Err(syn::Error::new(
+                        span,
+                        "64-bit formats can only be used for storage images and storage texel buffers",
+                    ));
+                }
                 let _right: syn::Token![>] = input.parse()?;
-                Self::StorageImage { format }
+                Self::UniformTexelBuffer { format }
+            }
+            "StorageTexelBuffer" => {
+                let _left: syn::Token![<] = input.parse()?;
+                let span = input.span();
+                let format: ImageFormat = input.parse()?;
+                check_storage_format(format, span)?;
+                let _right: syn::Token![>] = input.parse()?;
+                Self::StorageTexelBuffer { format }
+            }
+            "InputAttachment" => {
+                let _left: syn::Token![<] = input.parse()?;
+                let ident = input.parse::<syn::Ident>()?;
+                if ident != "index" {
+                    return Err(syn::Error::new(ident.span(), "Expected `index`"));
+                }
+                let _eq: syn::Token![=] = input.parse()?;
+                let index = input.parse::<syn::LitInt>()?.base10_parse()?;
+                let mut component = None;
+                let mut multisampled = None;
+                while input.peek(syn::Token![,]) {
+                    let _comma: syn::Token![,] = input.parse()?;
+                    let ident: syn::Ident = input.fork().parse()?;
+                    if let Ok(ty) = input.fork().parse::<PrimitiveTypeSingle>() {
+                        input.parse::<PrimitiveTypeSingle>()?;
+                        let Some(ty) = ImageComponentType::from_primitive(ty) else {
+                            return Err(syn::Error::new(
+                                ident.span(),
+                                "Image components must be f32, i32 or u32",
+                            ));
+                        };
+                        if component.replace(ty).is_some() {
+                            return Err(syn::Error::new(ident.span(), "Duplicate component type"));
+                        }
+                        continue;
+                    }
+                    let ident: syn::Ident = input.parse()?;
+                    let value = match ImageDimension::from_name(&ident.to_string()) {
+                        Some(ImageDimension::Dim2) => false,
+                        Some(ImageDimension::Dim2MS) => true,
+                        _ => {
+                            return Err(syn::Error::new(
+                                ident.span(),
+                                "Input attachments are Dim2 or Dim2MS",
+                            ))
+                        }
+                    };
+                    if multisampled.replace(value).is_some() {
+                        return Err(syn::Error::new(ident.span(), "Duplicate image dimension"));
+                    }
+                }
+                let _right: syn::Token![>] = input.parse()?;
+                Self::InputAttachment {
+                    index,
+                    component: component.unwrap_or_default(),
+                    multisampled: multisampled.unwrap_or_default(),
+                }
             }
-            "SampledImage" => Self::SampledImage,
-            "CombinedImageSampler" => Self::CombinedImageSampler,
-            "AccelerationStructure" => Self::AccelerationStructure,
             "UniformBuffer" => {
                 let _left: syn::Token![<] = input.parse()?;
                 let ty: Type = input.parse()?;
@@ -33,63 +179,191 @@ impl Parse for DescriptorType {
                 let _right: syn::Token![>] = input.parse()?;
                 Self::StorageBuffer { ty }
             }
+            "UniformBufferDynamic" => {
+                let _left: syn::Token![<] = input.parse()?;
+                let ty: Type = input.parse()?;
+                let _right: syn::Token![>] = input.parse()?;
+                Self::UniformBufferDynamic { ty }
+            }
+            "StorageBufferDynamic" => {
+                let _left: syn::Token![<] = input.parse()?;
+                let ty: Type = input.parse()?;
+                let _right: syn::Token![>] = input.parse()?;
+                Self::StorageBufferDynamic { ty }
+            }
             "InlineUniformBlock" => {
                 let _left: syn::Token![<] = input.parse()?;
                 let ty: Type = input.parse()?;
                 let _right: syn::Token![>] = input.parse()?;
                 Self::InlineUniformBlock { ty }
             }
+            "Mutable" => {
+                let _left: syn::Token![<] = input.parse()?;
+                let mut types: Vec<DescriptorType> = Vec::new();
+                loop {
+                    let span = input.span();
+                    let member: DescriptorType = input.parse()?;
+                    if matches!(
+                        member,
+                        DescriptorType::Mutable { .. }
+                            | DescriptorType::UniformBufferDynamic { .. }
+                            | DescriptorType::StorageBufferDynamic { .. }
+                            | DescriptorType::InlineUniformBlock { .. }
+                    ) {
+                        return Err(syn::Error::new(
+                            span,
+                            format!(
+                                "{} can't be a member of a mutable descriptor",
+                                member.name()
+                            ),
+                        ));
+                    }
+                    if types.iter().any(|ty| ty.same_type_as(&member)) {
+                        return Err(syn::Error::new(
+                            span,
+                            format!(
+                                "{} is already a member of this mutable descriptor",
+                                member.name()
+                            ),
+                        ));
+                    }
+                    types.push(member);
+                    if !input.peek(syn::Token![|]) {
+                        break;
+                    }
+                    let _or: syn::Token![|] = input.parse()?;
+                }
+                let _right: syn::Token![>] = input.parse()?;
+                Self::Mutable { types }
+            }
             _ => return Err(syn::Error::new(input.span(), "Invalid descriptor type")),
         };
         Ok(ty)
     }
 }
 
+/// Optional `<Dim3, u32, Shadow, R32_UInt>` arguments of image and sampler descriptors,
+/// in any order.
+#[derive(Default)]
+struct ImageArgs {
+    dimension: Option<(ImageDimension, proc_macro2::Span)>,
+    component: Option<(ImageComponentType, proc_macro2::Span)>,
+    shadow: Option<proc_macro2::Span>,
+    format: Option<(ImageFormat, proc_macro2::Span)>,
+    /// `Unknown`, for storage images without a format
+    unknown_format: Option<proc_macro2::Span>,
+}
+
+impl ImageArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = ImageArgs::default();
+        if !input.peek(syn::Token![<]) {
+            return Ok(args);
+        }
+        let _left: syn::Token![<] = input.parse()?;
+        loop {
+            let ident: syn::Ident = input.fork().parse()?;
+            if let Some(dimension) = ImageDimension::from_name(&ident.to_string()) {
+                if args.dimension.is_some() {
+                    return Err(syn::Error::new(ident.span(), "Duplicate image dimension"));
+                }
+                args.dimension = Some((dimension, ident.span()));
+                input.parse::<syn::Ident>()?;
+            } else if let Ok(ty) = input.fork().parse::<PrimitiveTypeSingle>() {
+                let Some(component) = ImageComponentType::from_primitive(ty) else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "Image components must be f32, i32 or u32",
+                    ));
+                };
+                if args.component.is_some() {
+                    return Err(syn::Error::new(ident.span(), "Duplicate component type"));
+                }
+                args.component = Some((component, ident.span()));
+                input.parse::<syn::Ident>()?;
+            } else if ident == "Unknown" {
+                args.unknown_format = Some(ident.span());
+                input.parse::<syn::Ident>()?;
+            } else if ident == "Shadow" {
+                args.shadow = Some(ident.span());
+                input.parse::<syn::Ident>()?;
+            } else {
+                if args.format.is_some() {
+                    return Err(syn::Error::new(ident.span(), "Duplicate image format"));
+                }
+                args.format = Some((input.parse()?, ident.span()));
+            }
+            if input.peek(syn::Token![>]) {
+                break;
+            }
+            let _comma: syn::Token![,] = input.parse()?;
+        }
+        let _right: syn::Token![>] = input.parse()?;
+        Ok(args)
+    }
+    fn dimension(&self) -> ImageDimension {
+        self.dimension
+            .map(|(dimension, _)| dimension)
+            .unwrap_or_default()
+    }
+    fn component(&self) -> ImageComponentType {
+        self.component
+            .map(|(component, _)| component)
+            .unwrap_or_default()
+    }
+    fn reject_component(&self) -> syn::Result<()> {
+        match self.component {
+            Some((_, span)) => Err(syn::Error::new(
+                span,
+                "Only sampled and storage images have a component type",
+            )),
+            None => Ok(()),
+        }
+    }
+    fn reject_shadow(&self) -> syn::Result<()> {
+        match self.shadow {
+            Some(span) => Err(syn::Error::new(
+                span,
+                "Depth comparison is only available on samplers",
+            )),
+            None => Ok(()),
+        }
+    }
+    fn reject_format(&self) -> syn::Result<()> {
+        match self.format.map(|(_, span)| span).or(self.unknown_format) {
+            Some(span) => Err(syn::Error::new(
+                span,
+                "Only storage images and texel buffers have a format",
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
 impl Parse for ImageFormat {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let ident = input.parse::<syn::Ident>()?;
-        let value = match ident.to_string().as_str() {
-            "RGBA32_Float" => Self::RGBA32_Float,
-            "RGBA16_Float" => Self::RGBA16_Float,
-            "RG32_Float" => Self::RG32_Float,
-            "RG16_Float" => Self::RG16_Float,
-            "R11G11B10_Float" => Self::R11G11B10_Float,
-            "R32_Float" => Self::R32_Float,
-            "R16_Float" => Self::R16_Float,
-            "RGBA16_UNorm" => Self::RGBA16_UNorm,
-            "RGB10A2_UNorm" => Self::RGB10A2_UNorm,
-            "RGBA8_UNorm" => Self::RGBA8_UNorm,
-            "RG16_UNorm" => Self::RG16_UNorm,
-            "RG8_UNorm" => Self::RG8_UNorm,
-            "R16_UNorm" => Self::R16_UNorm,
-            "R8_UNorm" => Self::R8_UNorm,
-            "RGBA16_SNorm" => Self::RGBA16_SNorm,
-            "RBGA8_SNorm" => Self::RBGA8_SNorm,
-            "RG16_SNorm" => Self::RG16_SNorm,
-            "RG8_SNorm" => Self::RG8_SNorm,
-            "R16_SNorm" => Self::R16_SNorm,
-            "R8_SNorm" => Self::R8_SNorm,
-            "RGBA32_SInt" => Self::RGBA32_SInt,
-            "RGBA16_SInt" => Self::RGBA16_SInt,
-            "RGBA8_SInt" => Self::RGBA8_SInt,
-            "RG32_SInt" => Self::RG32_SInt,
-            "RG16_SInt" => Self::RG16_SInt,
-            "RG8_SInt" => Self::RG8_SInt,
-            "R32_SInt" => Self::R32_SInt,
-            "R16_SInt" => Self::R16_SInt,
-            "R8_SInt" => Self::R8_SInt,
-            "RGBA32_UInt" => Self::RGBA32_UInt,
-            "RGBA16_UInt" => Self::RGBA16_UInt,
-            "RGBA8_UInt" => Self::RGBA8_UInt,
-            "RG32_UInt" => Self::RG32_UInt,
-            "RG16_UInt" => Self::RG16_UInt,
-            "RG8_UInt" => Self::RG8_UInt,
-            "R32_UInt" => Self::R32_UInt,
-            "R16_UInt" => Self::R16_UInt,
-            "R8_UInt" => Self::R8_UInt,
-            _ => return Err(syn::Error::new(ident.span(), "Invalid image layout")),
-        };
-        Ok(value)
+        let name = ident.to_string();
+        match name.as_str() {
+            // Vulkan name of the packed float format
+            "B10G11R11_Float" => Ok(Self::R11G11B10_Float),
+            // Misspelling accepted by earlier versions
+            "RBGA8_SNorm" => Ok(Self::RGBA8_SNorm),
+            _ => Self::from_name(&name)
+                .ok_or_else(|| syn::Error::new(ident.span(), "Invalid image layout")),
+        }
+    }
+}
+
+/// Storage images and storage texel buffers need a format with a GLSL format qualifier.
+fn check_storage_format(format: ImageFormat, span: proc_macro2::Span) -> syn::Result<()> {
+    if format.supports_storage() {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!("{} can't be used for storage", format.name()),
+        ))
     }
 }
 
@@ -143,6 +417,12 @@ impl Parse for Binding {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut binding: u32 = 0;
         let mut layout: Option<String> = None;
+        let mut memory = MemoryQualifiers::empty();
+        let mut memory_span = None;
+        let mut flags = BindingFlags::empty();
+        let mut variable_count: Option<(Option<u32>, proc_macro2::Span)> = None;
+        let mut immutable_sampler: Option<(String, proc_macro2::Span)> = None;
+        let mut aliases: Vec<(DescriptorAlias, proc_macro2::Span)> = Vec::new();
         loop {
             if !input.peek(syn::Token![#]) {
                 break;
@@ -159,6 +439,43 @@ impl Parse for Binding {
                 let _eq = content.parse::<syn::Token![=]>()?;
                 let layout_ident: syn::Ident = content.parse::<syn::Ident>()?;
                 layout = Some(layout_ident.to_string());
+            } else if ident == "update_after_bind" {
+                flags |= BindingFlags::UPDATE_AFTER_BIND;
+            } else if ident == "update_unused_while_pending" {
+                flags |= BindingFlags::UPDATE_UNUSED_WHILE_PENDING;
+            } else if ident == "partially_bound" {
+                flags |= BindingFlags::PARTIALLY_BOUND;
+            } else if ident == "variable_count" {
+                // `#[variable_count = N]` sets the upper bound of unsized arrays
+                let bound = if content.peek(syn::Token![=]) {
+                    let _eq = content.parse::<syn::Token![=]>()?;
+                    Some(content.parse::<syn::LitInt>()?.base10_parse()?)
+                } else {
+                    None
+                };
+                variable_count = Some((bound, ident.span()));
+                flags |= BindingFlags::VARIABLE_DESCRIPTOR_COUNT;
+            } else if ident == "alias" {
+                let alias;
+                let _paren: syn::token::Paren = syn::parenthesized!(alias in content);
+                let alias_ident = alias.parse::<syn::Ident>()?;
+                let _colon: syn::Token![:] = alias.parse()?;
+                let descriptor_type: DescriptorType = alias.parse()?;
+                aliases.push((
+                    DescriptorAlias {
+                        ident: alias_ident.to_string(),
+                        descriptor_type,
+                    },
+                    alias_ident.span(),
+                ));
+            } else if ident == "immutable" {
+                let sampler;
+                let _paren: syn::token::Paren = syn::parenthesized!(sampler in content);
+                let sampler = sampler.parse::<syn::Ident>()?;
+                immutable_sampler = Some((sampler.to_string(), ident.span()));
+            } else if let Some(qualifier) = parse_memory_qualifier(&ident) {
+                memory |= qualifier;
+                memory_span = Some(ident.span());
             } else {
                 return Err(syn::Error::new(ident.span(), "unknown attribute"));
             }
@@ -175,16 +492,94 @@ impl Parse for Binding {
         let mut descriptor_count = 1;
         let descriptor_type: DescriptorType = if input.peek(syn::token::Bracket) {
             let content;
-            let _bracket: syn::token::Bracket = syn::bracketed!(content in input);
+            let bracket: syn::token::Bracket = syn::bracketed!(content in input);
             let ty: DescriptorType = content.parse()?;
-            let _semicolon: syn::Token![;] = content.parse()?;
-            let length = content.parse::<syn::LitInt>()?;
-            descriptor_count = length.base10_parse()?;
+            if content.is_empty() {
+                // Unsized array, bounded by `#[variable_count = N]`
+                let Some((Some(bound), _)) = variable_count else {
+                    return Err(syn::Error::new(
+                        bracket.span.join(),
+                        "Unsized descriptor arrays need an upper bound, as in `#[variable_count = 1024]`",
+                    ));
+                };
+                flags |= BindingFlags::VARIABLE_DESCRIPTOR_COUNT;
+                descriptor_count = bound;
+            } else {
+                let _semicolon: syn::Token![;] = content.parse()?;
+                let length = content.parse::<syn::LitInt>()?;
+                descriptor_count = length.base10_parse()?;
+                if let Some((Some(_), span)) = variable_count {
+                    return Err(syn::Error::new(
+                        span,
+                        "The upper bound of sized descriptor arrays is their length",
+                    ));
+                }
+            }
             ty
         } else {
+            if let Some((_, span)) = variable_count {
+                return Err(syn::Error::new(
+                    span,
+                    "Variable descriptor counts require a descriptor array",
+                ));
+            }
             input.parse()?
         };
 
+        if let Some(span) = memory_span {
+            if !descriptor_type.is_writable() {
+                return Err(syn::Error::new(
+                    span,
+                    "Memory qualifiers can only be applied to storage buffers, storage images and storage texel buffers",
+                ));
+            }
+            if memory.contains(MemoryQualifiers::READONLY | MemoryQualifiers::WRITEONLY) {
+                return Err(syn::Error::new(
+                    span,
+                    "A binding can't be both readonly and writeonly",
+                ));
+            }
+        }
+
+        if let Some((_, span)) = &immutable_sampler {
+            if !matches!(
+                descriptor_type,
+                DescriptorType::Sampler { .. } | DescriptorType::CombinedImageSampler { .. }
+            ) {
+                return Err(syn::Error::new(
+                    *span,
+                    "Immutable samplers can only be applied to samplers and combined image samplers",
+                ));
+            }
+        }
+
+        for (alias, span) in aliases.iter() {
+            // Aliases only change how shaders see the descriptors, not the descriptor type.
+            if !descriptor_type
+                .members()
+                .iter()
+                .any(|member| member.same_type_as(&alias.descriptor_type))
+            {
+                return Err(syn::Error::new(
+                    *span,
+                    format!(
+                        "Aliases must have the descriptor type of the binding, but `{}` is a {}",
+                        alias.ident,
+                        alias.descriptor_type.name()
+                    ),
+                ));
+            }
+            if ident == alias.ident
+                || aliases
+                    .iter()
+                    .filter(|(other, _)| other.ident == alias.ident)
+                    .count()
+                    > 1
+            {
+                return Err(syn::Error::new(*span, "Duplicated alias name"));
+            }
+        }
+
         Ok(Binding {
             ident: ident.to_string(),
             binding,
@@ -192,6 +587,10 @@ impl Parse for Binding {
             descriptor_type,
             descriptor_count,
             layout,
+            memory,
+            flags,
+            immutable_sampler: immutable_sampler.map(|(sampler, _)| sampler),
+            aliases: aliases.into_iter().map(|(alias, _)| alias).collect(),
         })
     }
 }
@@ -239,6 +638,35 @@ impl Parse for PushConstantsLayout {
     }
 }
 
+/// The names a binding declares in shaders: the binding or the members of a mutable binding,
+/// its aliases, and the blocks of buffers.
+fn declared_names(binding: &Binding) -> Vec<String> {
+    let mut declarations = vec![(binding.ident.clone(), &binding.descriptor_type)];
+    if let DescriptorType::Mutable { types } = &binding.descriptor_type {
+        declarations.extend(types.iter().map(|member| {
+            let ident = format!("{}_{}", binding.ident, member.name().to_snake_case());
+            (ident, member)
+        }));
+    }
+    declarations.extend(
+        binding
+            .aliases
+            .iter()
+            .map(|alias| (alias.ident.clone(), &alias.descriptor_type)),
+    );
+    let mut names = Vec::new();
+    for (ident, descriptor_type) in declarations {
+        if descriptor_type.block_type().is_some() {
+            let block = ident.to_pascal_case();
+            if block != ident {
+                names.push(block);
+            }
+        }
+        names.push(ident);
+    }
+    names
+}
+
 impl Parse for SetLayout {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let _struct = input.parse::<syn::Token![struct]>()?;
@@ -251,6 +679,8 @@ impl Parse for SetLayout {
             let mut current_shader_stages = ShaderStages::empty();
             let mut current_binding = 0;
             let mut bindings = Vec::new();
+            let mut variable_count: Option<(u32, proc_macro2::Span)> = None;
+            let mut names: BTreeSet<String> = BTreeSet::new();
             loop {
                 if content.peek(syn::Token![#]) && content.peek2(syn::Token![!]) {
                     current_shader_stages = parse_shader_stage_attribute(&content)?;
@@ -265,6 +695,7 @@ impl Parse for SetLayout {
                         "No shader stages specified for this binding",
                     ));
                 }
+                let span = content.span();
                 let mut binding: Binding = content.parse()?;
                 if binding.binding == 0 {
                     // If the binding number wasn't specified, use the automatically tracked number
@@ -274,6 +705,29 @@ impl Parse for SetLayout {
                     current_binding = binding.binding;
                 }
                 binding.stages = current_shader_stages;
+                if binding
+                    .flags
+                    .contains(BindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+                {
+                    if variable_count.is_some() {
+                        return Err(syn::Error::new(
+                            span,
+                            "Only one binding per set can have a variable descriptor count",
+                        ));
+                    }
+                    variable_count = Some((binding.binding, span));
+                }
+                for declared in declared_names(&binding) {
+                    if !names.insert(declared.clone()) {
+                        return Err(syn::Error::new(
+                            span,
+                            format!(
+                                "`{}` is already declared by a binding, alias or block in set `{}`",
+                                declared, name
+                            ),
+                        ));
+                    }
+                }
                 bindings.push(binding);
                 if content.is_empty() {
                     break;
@@ -281,10 +735,20 @@ impl Parse for SetLayout {
                 let _comma: syn::Token![,] = content.parse()?;
                 current_binding += 1;
             }
+            // Vulkan only allows a variable count on the last binding of a set
+            if let Some((variable, span)) = variable_count {
+                if bindings.iter().any(|binding| binding.binding > variable) {
+                    return Err(syn::Error::new(
+                        span,
+                        "Only the binding with the highest binding number can have a variable descriptor count",
+                    ));
+                }
+            }
             Ok(SetLayout {
                 bindings,
                 name: name.to_string(),
                 set: 0,
+                push_descriptor: false,
             })
         } else {
             Err(lookahead.error())
@@ -296,19 +760,42 @@ impl Parse for PlayoutModule {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut module = PlayoutModule::default();
         let mut current_set_id: u32 = 0;
+        let mut alias_spans: Vec<(String, proc_macro2::Span)> = Vec::new();
+        // Spans of structs, descriptor sets and push constants for reporting layout errors
+        let mut spans: BTreeMap<String, proc_macro2::Span> = BTreeMap::new();
         loop {
             if input.is_empty() {
                 break;
             }
 
             let mut is_descriptor_set = None;
+            let mut push_descriptor = false;
             let mut is_push_constants = false;
-            if input.peek(syn::Token![#]) {
+            let mut matrix_layout = None;
+            let mut align = None;
+            while input.peek(syn::Token![#]) {
                 let _pound: syn::Token![#] = input.parse()?;
                 let content;
                 let _bracket: syn::token::Bracket = syn::bracketed!(content in input);
                 let ident = content.parse::<syn::Ident>()?;
                 if ident == "set" {
+                    if content.peek(syn::token::Paren) {
+                        let options;
+                        let _paren: syn::token::Paren = syn::parenthesized!(options in content);
+                        for option in options
+                            .parse_terminated(syn::Ident::parse, syn::Token![,])?
+                            .iter()
+                        {
+                            if option == "push_descriptor" {
+                                push_descriptor = true;
+                            } else {
+                                return Err(syn::Error::new(
+                                    option.span(),
+                                    "unknown descriptor set option",
+                                ));
+                            }
+                        }
+                    }
                     if content.peek(syn::Token![=]) {
                         let _eq: syn::Token![=] = content.parse()?;
                         current_set_id = content.parse::<syn::LitInt>()?.base10_parse()?;
@@ -317,6 +804,10 @@ impl Parse for PlayoutModule {
                     current_set_id += 1;
                 } else if ident == "push_constants" {
                     is_push_constants = true;
+                } else if let Some(layout) = parse_matrix_layout(&ident) {
+                    matrix_layout = Some((layout, ident.span()));
+                } else if ident == "align" {
+                    align = Some((parse_align_attribute(&content)?, ident.span()));
                 } else {
                     return Err(syn::Error::new(ident.span(), "unknown attribute"));
                 }
@@ -324,25 +815,580 @@ impl Parse for PlayoutModule {
             if input.is_empty() {
                 break;
             }
-            if input.peek(syn::Token![struct]) {
+            if let Some((_, span)) = align {
+                if is_descriptor_set.is_some()
+                    || is_push_constants
+                    || !input.peek(syn::Token![struct])
+                {
+                    return Err(syn::Error::new(
+                        span,
+                        "Alignment can only be specified on data structs",
+                    ));
+                }
+            }
+            let lookahead = input.lookahead1();
+            let span = input.span();
+            if lookahead.peek(syn::Token![struct]) {
                 if let Some(set_id) = is_descriptor_set {
+                    if let Some((_, span)) = matrix_layout {
+                        return Err(syn::Error::new(
+                            span,
+                            "Matrix layout cannot be specified on descriptor sets",
+                        ));
+                    }
                     let mut set_layout = input.parse::<SetLayout>()?;
                     set_layout.set = set_id;
+                    set_layout.push_descriptor = push_descriptor;
+                    if push_descriptor {
+                        validate_push_descriptor_set(&set_layout, span)?;
+                    }
+                    spans.insert(set_layout.name.clone(), span);
                     module.descriptor_sets.push(set_layout);
                 } else if is_push_constants {
-                    module.push_constants = input.parse()?;
+                    let mut push_constants: PushConstantsLayout = input.parse()?;
+                    spans.insert(push_constants.name.clone(), span);
+                    if let Some((layout, _)) = matrix_layout {
+                        for field in push_constants.fields.iter_mut() {
+                            field.field.matrix_layout.get_or_insert(layout);
+                        }
+                    }
+                    module.push_constants = push_constants;
                 } else {
-                    let data_struct = input.parse::<DataStruct>()?;
+                    let mut data_struct = input.parse::<DataStruct>()?;
+                    data_struct.align = align.map(|(align, _)| align);
+                    spans.insert(data_struct.ident.clone(), span);
+                    if let Some((layout, _)) = matrix_layout {
+                        // Struct level matrix layout applies to all fields without their own.
+                        for field in data_struct.fields.iter_mut() {
+                            field.matrix_layout.get_or_insert(layout);
+                        }
+                    }
                     module
                         .data_structs
                         .insert(data_struct.ident.clone(), data_struct);
                 }
+            } else if let Some((_, span)) = matrix_layout {
+                return Err(syn::Error::new(
+                    span,
+                    "Matrix layout can only be specified on structs",
+                ));
+            } else if lookahead.peek(syn::Token![enum]) {
+                if is_descriptor_set.is_some() || is_push_constants {
+                    return Err(syn::Error::new(
+                        input.span(),
+                        "Descriptor sets and push constants must be declared as structs",
+                    ));
+                }
+                let data_enum = input.parse::<DataEnum>()?;
+                module.data_enums.insert(data_enum.ident.clone(), data_enum);
+            } else if input
+                .cursor()
+                .ident()
+                .is_some_and(|(ident, _)| ident == "sampler")
+            {
+                if is_descriptor_set.is_some() || is_push_constants {
+                    return Err(syn::Error::new(
+                        input.span(),
+                        "Descriptor sets and push constants must be declared as structs",
+                    ));
+                }
+                let sampler = input.parse::<ImmutableSampler>()?;
+                if module.samplers.contains_key(&sampler.ident) {
+                    return Err(syn::Error::new(span, "Duplicated sampler"));
+                }
+                module.samplers.insert(sampler.ident.clone(), sampler);
+            } else if lookahead.peek(syn::Token![type]) {
+                if is_descriptor_set.is_some() || is_push_constants {
+                    return Err(syn::Error::new(
+                        input.span(),
+                        "Descriptor sets and push constants must be declared as structs",
+                    ));
+                }
+                let span = input.span();
+                let alias = input.parse::<TypeAlias>()?;
+                alias_spans.push((alias.ident.clone(), span));
+                module.type_aliases.insert(alias.ident.clone(), alias);
+            } else {
+                return Err(lookahead.error());
+            }
+        }
+        for (ident, span) in alias_spans {
+            // Aliases may refer to items declared later in the file, so cycles can only be
+            // detected once everything was parsed.
+            let mut current = ident.as_str();
+            for _ in 0..module.type_aliases.len() {
+                match alias_target(&module.type_aliases[current].ty) {
+                    Some(path) if module.type_aliases.contains_key(path) => {
+                        current = path;
+                    }
+                    _ => break,
+                }
+                if current == ident {
+                    return Err(syn::Error::new(span, "Type alias refers to itself"));
+                }
             }
         }
+        validate_struct_cycles(&module, &spans)?;
+        validate_layouts(&module, &spans)?;
+        validate_explicit_layouts(&module, &spans)?;
+        validate_samplers(&module, &spans)?;
         Ok(module)
     }
 }
 
+/// The named type an alias refers to, looking through arrays, slices and pointers.
+fn alias_target(ty: &Type) -> Option<&String> {
+    match ty.pointee() {
+        Some(pointee) => alias_target(pointee),
+        None => ty.base_nonprimitive_type(),
+    }
+}
+
+/// Structs can't contain themselves, directly or through arrays, slices, aliases and other
+/// structs. Pointers only hold an address, so a struct may point to itself.
+fn validate_struct_cycles(
+    module: &PlayoutModule,
+    spans: &BTreeMap<String, proc_macro2::Span>,
+) -> syn::Result<()> {
+    fn contained_struct<'a>(module: &'a PlayoutModule, ty: &'a Type) -> Option<&'a String> {
+        let path = ty.base_nonprimitive_type()?;
+        match module.type_aliases.get(path) {
+            Some(alias) => contained_struct(module, &alias.ty),
+            None => module.data_structs.contains_key(path).then_some(path),
+        }
+    }
+    fn visit<'a>(
+        module: &'a PlayoutModule,
+        ident: &'a str,
+        visiting: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+    ) -> Result<(), &'a str> {
+        if done.contains(ident) {
+            return Ok(());
+        }
+        if visiting.contains(&ident) {
+            return Err(ident);
+        }
+        visiting.push(ident);
+        for field in module.data_structs[ident].fields.iter() {
+            if let Some(path) = contained_struct(module, &field.ty) {
+                visit(module, path, visiting, done)?;
+            }
+        }
+        visiting.pop();
+        done.insert(ident);
+        Ok(())
+    }
+
+    let mut done = BTreeSet::new();
+    for ident in module.data_structs.keys() {
+        visit(module, ident, &mut Vec::new(), &mut done).map_err(|ident| {
+            syn::Error::new(spans[ident], format!("Struct `{}` contains itself", ident))
+        })?;
+    }
+    Ok(())
+}
+
+/// Check that explicit offsets and alignments are valid under the block layouts in use.
+fn validate_layouts(
+    module: &PlayoutModule,
+    spans: &BTreeMap<String, proc_macro2::Span>,
+) -> syn::Result<()> {
+    let error = |name: &str, err: LayoutError| syn::Error::new(spans[name], err.message);
+    for data_struct in module.data_structs.values() {
+        data_struct
+            .layout_in(module, BlockLayout::default(), MatrixLayout::default())
+            .map_err(|err| error(&data_struct.ident, err))?;
+        for field in data_struct.fields.iter() {
+            validate_pointer(module, &field.ty).map_err(|err| error(&data_struct.ident, err))?;
+            field
+                .ty
+                .matrix_layout_in(module, field.matrix_layout.unwrap_or_default())
+                .map_err(|err| error(&data_struct.ident, err))?;
+        }
+    }
+    for set in module.descriptor_sets.iter() {
+        for binding in set.bindings.iter() {
+            for member in binding.declared_types() {
+                if let Some(ty) = member.block_type() {
+                    ty.layout_in(
+                        module,
+                        binding.block_layout_of(member),
+                        MatrixLayout::default(),
+                    )
+                    .map_err(|err| error(&set.name, err))?;
+                    validate_pointer(module, ty).map_err(|err| error(&set.name, err))?;
+                    // Blocks of a struct type take the struct fields as members, checked above.
+                    if !matches!(ty.resolve(module), Type::Path(path) if module.data_structs.contains_key(path))
+                    {
+                        ty.matrix_layout_in(module, MatrixLayout::default())
+                            .map_err(|err| error(&set.name, err))?;
+                    }
+                }
+            }
+        }
+    }
+    if !module.push_constants.fields.is_empty() {
+        module
+            .push_constants
+            .layout_in(module, BlockLayout::default())
+            .map_err(|err| error(&module.push_constants.name, err))?;
+        for field in module.push_constants.fields.iter() {
+            validate_pointer(module, &field.field.ty)
+                .map_err(|err| error(&module.push_constants.name, err))?;
+            field
+                .field
+                .ty
+                .matrix_layout_in(module, field.field.matrix_layout.unwrap_or_default())
+                .map_err(|err| error(&module.push_constants.name, err))?;
+        }
+    }
+    Ok(())
+}
+
+/// GLSL only takes `offset` and `align` qualifiers on block members. Structs with explicit
+/// field offsets or alignments must be used directly as block types, and structs with an
+/// explicit alignment directly as block members.
+fn validate_explicit_layouts(
+    module: &PlayoutModule,
+    spans: &BTreeMap<String, proc_macro2::Span>,
+) -> syn::Result<()> {
+    // Structs used directly as block members, and structs nested in anything else.
+    let mut members: BTreeSet<&str> = BTreeSet::new();
+    let mut nested: BTreeSet<&str> = BTreeSet::new();
+    fn visit<'a>(
+        module: &'a PlayoutModule,
+        ty: &'a Type,
+        member: bool,
+        members: &mut BTreeSet<&'a str>,
+        nested: &mut BTreeSet<&'a str>,
+    ) {
+        match ty.resolve(module) {
+            Type::Array { ty, .. } | Type::Slice { ty } | Type::Ptr { ty } => {
+                visit(module, ty, false, members, nested)
+            }
+            Type::Path(path) => {
+                let Some(data_struct) = module.data_structs.get(path) else {
+                    return;
+                };
+                let first_use = if member {
+                    members.insert(path)
+                } else {
+                    nested.insert(path)
+                };
+                if first_use {
+                    for field in data_struct.fields.iter() {
+                        visit(module, &field.ty, false, members, nested);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    for set in module.descriptor_sets.iter() {
+        for binding in set.bindings.iter() {
+            for ty in binding
+                .declared_types()
+                .filter_map(|member| member.block_type())
+            {
+                match ty.resolve(module) {
+                    Type::Path(path) if module.data_structs.contains_key(path) => {
+                        for field in module.data_structs[path].fields.iter() {
+                            visit(module, &field.ty, true, &mut members, &mut nested);
+                        }
+                    }
+                    ty => visit(module, ty, true, &mut members, &mut nested),
+                }
+            }
+        }
+    }
+    for field in module.push_constants.fields.iter() {
+        visit(module, &field.field.ty, true, &mut members, &mut nested);
+    }
+
+    for name in members.iter().chain(nested.iter()) {
+        let data_struct = &module.data_structs[*name];
+        if data_struct
+            .fields
+            .iter()
+            .any(|field| field.offset.is_some() || field.align.is_some())
+        {
+            return Err(syn::Error::new(
+                spans[*name],
+                format!(
+                    "Struct `{}` has fields with explicit offsets or alignments, which GLSL only supports on block members, so it can only be used directly as a block type",
+                    name
+                ),
+            ));
+        }
+    }
+    for name in nested.iter() {
+        if module.data_structs[*name].align.is_some() {
+            return Err(syn::Error::new(
+                spans[*name],
+                format!(
+                    "Struct `{}` has an explicit alignment, which GLSL only supports on block members, so it can't be nested in arrays, pointers or other structs",
+                    name
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Pointees are laid out as `std430` arrays, so they must be sized and known.
+fn validate_pointer(module: &PlayoutModule, ty: &Type) -> Result<(), LayoutError> {
+    let Some(pointee) = ty.pointee() else {
+        return Ok(());
+    };
+    pointee.layout_in(module, BlockLayout::Std430, MatrixLayout::default())?;
+    pointee.matrix_layout_in(module, MatrixLayout::default())?;
+    let runtime_sized = match pointee.resolve(module) {
+        Type::Array { .. } | Type::Slice { .. } => true,
+        Type::Path(path) => module.data_structs.get(path).is_some_and(|data_struct| {
+            data_struct
+                .fields
+                .last()
+                .is_some_and(|field| matches!(field.ty.resolve(module), Type::Slice { .. }))
+        }),
+        _ => false,
+    };
+    if runtime_sized {
+        return Err(LayoutError {
+            message: "Pointers must point to sized non-array types, use `Ptr<[T]>` for arrays"
+                .to_string(),
+        });
+    }
+    validate_pointer(module, pointee)
+}
+
+/// Check the restrictions `VK_KHR_push_descriptor` puts on set layouts.
+fn validate_push_descriptor_set(set: &SetLayout, span: proc_macro2::Span) -> syn::Result<()> {
+    for binding in set.bindings.iter() {
+        if matches!(
+            binding.descriptor_type,
+            DescriptorType::UniformBufferDynamic { .. }
+                | DescriptorType::StorageBufferDynamic { .. }
+        ) {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Push descriptor sets can't have dynamic buffers, but `{}` is one",
+                    binding.ident
+                ),
+            ));
+        }
+        if let DescriptorType::Mutable { .. } = binding.descriptor_type {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Push descriptor sets can't have mutable descriptors, but `{}` is one",
+                    binding.ident
+                ),
+            ));
+        }
+        if binding
+            .flags
+            .intersects(BindingFlags::UPDATE_AFTER_BIND | BindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+        {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Push descriptor sets can't have update-after-bind or variable count bindings, but `{}` is one",
+                    binding.ident
+                ),
+            ));
+        }
+    }
+    let count: u64 = set
+        .bindings
+        .iter()
+        .map(|binding| binding.descriptor_count as u64)
+        .sum();
+    if count > SetLayout::MAX_PUSH_DESCRIPTORS as u64 {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "Push descriptor sets can have at most {} descriptors, but this one has {}",
+                SetLayout::MAX_PUSH_DESCRIPTORS,
+                count
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that immutable samplers exist and compare for shadow bindings.
+fn validate_samplers(
+    module: &PlayoutModule,
+    spans: &BTreeMap<String, proc_macro2::Span>,
+) -> syn::Result<()> {
+    for set in module.descriptor_sets.iter() {
+        for binding in set.bindings.iter() {
+            let Some(name) = &binding.immutable_sampler else {
+                continue;
+            };
+            let Some(sampler) = module.samplers.get(name) else {
+                return Err(syn::Error::new(
+                    spans[&set.name],
+                    format!("Unknown sampler `{}`", name),
+                ));
+            };
+            let shadow = matches!(
+                binding.descriptor_type,
+                DescriptorType::Sampler { shadow: true }
+                    | DescriptorType::CombinedImageSampler { shadow: true, .. }
+            );
+            if shadow && sampler.compare_op.is_none() {
+                return Err(syn::Error::new(
+                    spans[&set.name],
+                    format!(
+                        "Sampler `{}` needs a `compare_op` to be used by the shadow binding `{}`",
+                        name, binding.ident
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Parse for ImmutableSampler {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let _sampler = input.parse::<syn::Ident>()?;
+        let name = input.parse::<syn::Ident>()?;
+        let lookahead = input.lookahead1();
+        if !lookahead.peek(syn::token::Brace) {
+            return Err(lookahead.error());
+        }
+        let content;
+        let _paren: syn::token::Brace = syn::braced!(content in input);
+
+        let mut sampler = ImmutableSampler::new(name.to_string());
+        loop {
+            if content.is_empty() {
+                break;
+            }
+            let key = content.parse::<syn::Ident>()?;
+            let _colon: syn::Token![:] = content.parse()?;
+            match key.to_string().as_str() {
+                "filter" => {
+                    sampler.mag_filter = parse_sampler_enum(&content, Filter::from_name)?;
+                    sampler.min_filter = sampler.mag_filter;
+                }
+                "mag_filter" => {
+                    sampler.mag_filter = parse_sampler_enum(&content, Filter::from_name)?
+                }
+                "min_filter" => {
+                    sampler.min_filter = parse_sampler_enum(&content, Filter::from_name)?
+                }
+                "mipmap_mode" => {
+                    sampler.mipmap_mode =
+                        parse_sampler_enum(&content, SamplerMipmapMode::from_name)?
+                }
+                "address_mode" => {
+                    sampler.address_mode_u =
+                        parse_sampler_enum(&content, SamplerAddressMode::from_name)?;
+                    sampler.address_mode_v = sampler.address_mode_u;
+                    sampler.address_mode_w = sampler.address_mode_u;
+                }
+                "address_mode_u" => {
+                    sampler.address_mode_u =
+                        parse_sampler_enum(&content, SamplerAddressMode::from_name)?
+                }
+                "address_mode_v" => {
+                    sampler.address_mode_v =
+                        parse_sampler_enum(&content, SamplerAddressMode::from_name)?
+                }
+                "address_mode_w" => {
+                    sampler.address_mode_w =
+                        parse_sampler_enum(&content, SamplerAddressMode::from_name)?
+                }
+                "mip_lod_bias" => sampler.mip_lod_bias = parse_sampler_float(&content)?,
+                "max_anisotropy" => {
+                    let span = content.span();
+                    let max_anisotropy = parse_sampler_float(&content)?;
+                    if max_anisotropy < 1.0 {
+                        return Err(syn::Error::new(span, "Max anisotropy must be at least 1"));
+                    }
+                    sampler.max_anisotropy = Some(max_anisotropy);
+                }
+                "compare_op" => {
+                    sampler.compare_op = Some(parse_sampler_enum(&content, CompareOp::from_name)?)
+                }
+                "min_lod" => sampler.min_lod = parse_sampler_float(&content)?,
+                "max_lod" => sampler.max_lod = parse_sampler_float(&content)?,
+                "border_color" => {
+                    sampler.border_color = parse_sampler_enum(&content, BorderColor::from_name)?
+                }
+                "unnormalized_coordinates" => {
+                    sampler.unnormalized_coordinates = content.parse::<syn::LitBool>()?.value
+                }
+                _ => return Err(syn::Error::new(key.span(), "Unknown sampler parameter")),
+            }
+            if content.is_empty() {
+                break;
+            }
+            let _comma: syn::Token![,] = content.parse()?;
+        }
+
+        if sampler.min_lod > sampler.max_lod {
+            return Err(syn::Error::new(
+                name.span(),
+                "`min_lod` must not be greater than `max_lod`",
+            ));
+        }
+        if sampler.unnormalized_coordinates {
+            let clamped = |mode| {
+                matches!(
+                    mode,
+                    SamplerAddressMode::ClampToEdge | SamplerAddressMode::ClampToBorder
+                )
+            };
+            if sampler.mag_filter != sampler.min_filter
+                || sampler.mipmap_mode != SamplerMipmapMode::Nearest
+                || sampler.min_lod != 0.0
+                || sampler.max_lod != 0.0
+                || !clamped(sampler.address_mode_u)
+                || !clamped(sampler.address_mode_v)
+                || sampler.max_anisotropy.is_some()
+                || sampler.compare_op.is_some()
+            {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "Samplers with unnormalized coordinates need identical filters, a nearest mipmap mode, \
+                     `min_lod: 0` and `max_lod: 0`, clamped u and v addressing, and no anisotropy or compare",
+                ));
+            }
+        }
+        Ok(sampler)
+    }
+}
+
+fn parse_sampler_enum<T>(input: ParseStream, from_name: fn(&str) -> Option<T>) -> syn::Result<T> {
+    let ident = input.parse::<syn::Ident>()?;
+    from_name(&ident.to_string())
+        .ok_or_else(|| syn::Error::new(ident.span(), "Unknown sampler parameter value"))
+}
+
+fn parse_sampler_float(input: ParseStream) -> syn::Result<f32> {
+    let sign = if input.peek(syn::Token![-]) {
+        let _minus: syn::Token![-] = input.parse()?;
+        -1.0
+    } else {
+        1.0
+    };
+    let lookahead = input.lookahead1();
+    let value: f32 = if lookahead.peek(syn::LitFloat) {
+        input.parse::<syn::LitFloat>()?.base10_parse()?
+    } else if lookahead.peek(syn::LitInt) {
+        input.parse::<syn::LitInt>()?.base10_parse::<u32>()? as f32
+    } else {
+        return Err(lookahead.error());
+    };
+    Ok(sign * value)
+}
+
 impl Parse for PrimitiveTypeSingle {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let ident = input.parse::<syn::Ident>()?;
@@ -364,83 +1410,97 @@ impl Parse for PrimitiveTypeSingle {
     }
 }
 
+/// `Vec3`, `UVec2`, `BVec4`, ... => (component type, length)
+fn vec_shorthand(name: &str) -> Option<(PrimitiveTypeSingle, u8)> {
+    const PREFIXES: [(&str, PrimitiveTypeSingle); 6] = [
+        ("Vec", PrimitiveTypeSingle::F32),
+        ("UVec", PrimitiveTypeSingle::U32),
+        ("IVec", PrimitiveTypeSingle::I32),
+        ("BVec", PrimitiveTypeSingle::Bool),
+        ("DVec", PrimitiveTypeSingle::F64),
+        ("HVec", PrimitiveTypeSingle::F16),
+    ];
+    PREFIXES.iter().find_map(|(prefix, ty)| {
+        let length = dimension_shorthand(name.strip_prefix(prefix)?)?;
+        Some((*ty, length))
+    })
+}
+
+/// `Mat4`, `Mat3x4`, ... => (columns, rows). Follows the GLSL convention of `matCxR`.
+fn mat_shorthand(name: &str) -> Option<(u8, u8)> {
+    let size = name.strip_prefix("Mat")?;
+    match size.split_once('x') {
+        Some((columns, rows)) => Some((dimension_shorthand(columns)?, dimension_shorthand(rows)?)),
+        None => dimension_shorthand(size).map(|n| (n, n)),
+    }
+}
+
+fn dimension_shorthand(dimension: &str) -> Option<u8> {
+    match dimension {
+        "2" => Some(2),
+        "3" => Some(3),
+        "4" => Some(4),
+        _ => None,
+    }
+}
+
 impl Parse for PrimitiveType {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let old_input = input.fork();
 
         let ident = input.parse::<syn::Ident>()?;
-        let mut result = match ident.to_string().as_str() {
-            "Vec4" => Self::Vec {
-                ty: PrimitiveTypeSingle::F32,
-                length: 4,
-            },
-            "Vec3" => Self::Vec {
-                ty: PrimitiveTypeSingle::F32,
-                length: 3,
-            },
-            "Vec2" => Self::Vec {
-                ty: PrimitiveTypeSingle::F32,
-                length: 2,
-            },
-            "UVec4" => Self::Vec {
-                ty: PrimitiveTypeSingle::U32,
-                length: 4,
-            }, // Shorthands
-            "UVec3" => Self::Vec {
-                ty: PrimitiveTypeSingle::U32,
-                length: 3,
-            },
-            "UVec2" => Self::Vec {
-                ty: PrimitiveTypeSingle::U32,
-                length: 2,
-            },
-            "IVec4" => Self::Vec {
-                ty: PrimitiveTypeSingle::I32,
-                length: 4,
-            },
-            "IVec3" => Self::Vec {
-                ty: PrimitiveTypeSingle::I32,
-                length: 3,
-            },
-            "IVec2" => Self::Vec {
-                ty: PrimitiveTypeSingle::I32,
-                length: 2,
-            },
-            "Mat4" => Self::Mat {
+        let name = ident.to_string();
+        // Shorthands
+        let mut result = if let Some((ty, length)) = vec_shorthand(&name) {
+            Self::Vec { ty, length }
+        } else if let Some((columns, rows)) = mat_shorthand(&name) {
+            Self::Mat {
                 ty: PrimitiveTypeSingle::F32,
-                rows: 4,
-                columns: 4,
-            },
-            "Mat3" => Self::Mat {
-                ty: PrimitiveTypeSingle::F32,
-                rows: 3,
-                columns: 3,
-            },
-            "Mat2" => Self::Mat {
-                ty: PrimitiveTypeSingle::F32,
-                rows: 2,
-                columns: 2,
-            },
-            _ => Self::Single(old_input.parse()?),
+                rows,
+                columns,
+            }
+        } else {
+            Self::Single(old_input.parse()?)
         };
         if input.peek(syn::Token![<]) {
             // Attempt to resolve generic arg
-            if let Self::Vec { ty, .. } = &mut result {
-                if matches!(ty, PrimitiveTypeSingle::F32) {
+            match &mut result {
+                Self::Vec { ty, .. } | Self::Mat { ty, .. }
+                    if !matches!(ty, PrimitiveTypeSingle::F32) =>
+                {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "Generic type cannot be used on this shorthand type",
+                    ));
+                }
+                Self::Vec { ty, .. } => {
                     let _left = input.parse::<syn::Token![<]>()?;
                     *ty = input.parse()?;
                     let _right = input.parse::<syn::Token![>]>()?;
-                } else {
+                }
+                Self::Mat { ty, .. } => {
+                    let _left = input.parse::<syn::Token![<]>()?;
+                    let span = input.span();
+                    *ty = input.parse()?;
+                    if !matches!(
+                        ty,
+                        PrimitiveTypeSingle::F16
+                            | PrimitiveTypeSingle::F32
+                            | PrimitiveTypeSingle::F64
+                    ) {
+                        return Err(syn::Error::new(
+                            span,
+                            "Matrices must have floating point components",
+                        ));
+                    }
+                    let _right = input.parse::<syn::Token![>]>()?;
+                }
+                Self::Single(_) => {
                     return Err(syn::Error::new(
                         ident.span(),
-                        "Generic type cannot be used on this shorthand type",
+                        "Generic type cannot be used on primitive types",
                     ));
                 }
-            } else {
-                return Err(syn::Error::new(
-                    ident.span(),
-                    "Generic type cannot be used on primitive types",
-                ));
             }
         }
         Ok(result)
@@ -453,7 +1513,15 @@ impl Parse for Type {
             // Array-like
             let content;
             let _bracket = syn::bracketed!(content in input);
+            let span = content.span();
             let ty: Type = content.parse()?;
+            if let Type::Bitfield { .. } = ty {
+                // Accessors are only generated for bitfields declared as fields.
+                return Err(syn::Error::new(
+                    span,
+                    "Bitfields must be declared on a struct field",
+                ));
+            }
             if content.peek(syn::Token![;]) {
                 let _semicolon: syn::Token![;] = content.parse()?;
                 let length = content.parse::<syn::LitInt>()?;
@@ -465,8 +1533,45 @@ impl Parse for Type {
                 Ok(Type::Slice { ty: Box::new(ty) })
             }
         } else if let Ok(ty) = input.fork().parse::<PrimitiveType>() {
+            let span = input.span();
             input.parse::<PrimitiveType>()?;
+            if input.peek(syn::token::Brace) {
+                return match ty {
+                    PrimitiveType::Single(
+                        ty @ (PrimitiveTypeSingle::U8
+                        | PrimitiveTypeSingle::U16
+                        | PrimitiveTypeSingle::U32),
+                    ) => parse_bitfield(input, ty),
+                    _ => Err(syn::Error::new(
+                        span,
+                        "Bitfields must be backed by u8, u16 or u32",
+                    )),
+                };
+            }
             Ok(Type::Primitive(ty))
+        } else if input.peek(syn::Ident) && input.peek2(syn::Token![<]) {
+            let ident: syn::Ident = input.parse()?;
+            if ident != "Ptr" {
+                return Err(syn::Error::new(ident.span(), "Unknown generic type"));
+            }
+            input.parse::<syn::Token![<]>()?;
+            let span = input.span();
+            let ty: Type = input.parse()?;
+            input.parse::<syn::Token![>]>()?;
+            let element = match &ty {
+                Type::Slice { ty } => ty.as_ref(),
+                ty => ty,
+            };
+            if !matches!(
+                element,
+                Type::Primitive(_) | Type::Path(_) | Type::Ptr { .. }
+            ) {
+                return Err(syn::Error::new(
+                    span,
+                    "Pointers must point to a primitive, a named type or a pointer, or to a slice of them",
+                ));
+            }
+            Ok(Type::Ptr { ty: Box::new(ty) })
         } else {
             let path: syn::Path = input.parse()?;
             Ok(Type::Path(path.require_ident()?.to_string()))
@@ -474,14 +1579,126 @@ impl Parse for Type {
     }
 }
 
+fn parse_bitfield(input: ParseStream, ty: PrimitiveTypeSingle) -> syn::Result<Type> {
+    let content;
+    let brace: syn::token::Brace = syn::braced!(content in input);
+    let total_bits = ty.layout().size() as u32 * 8;
+    let mut offset: u32 = 0;
+    let mut members = Vec::new();
+    loop {
+        if content.is_empty() {
+            break;
+        }
+        let ident = content.parse::<syn::Ident>()?;
+        if ident.to_string().starts_with("r#") {
+            // Member names become Rust accessors and GLSL functions.
+            return Err(syn::Error::new(
+                ident.span(),
+                "Bitfield members can't be named after keywords",
+            ));
+        }
+        let _colon: syn::Token![:] = content.parse()?;
+        let bits_literal = content.parse::<syn::LitInt>()?;
+        let bits: u32 = bits_literal.base10_parse()?;
+        if bits == 0 {
+            return Err(syn::Error::new(
+                bits_literal.span(),
+                "Bitfield members must have at least one bit",
+            ));
+        }
+        if members
+            .iter()
+            .any(|member: &BitfieldMember| ident == member.ident)
+        {
+            return Err(syn::Error::new(ident.span(), "Duplicated bitfield member"));
+        }
+        members.push(BitfieldMember {
+            ident: ident.to_string(),
+            offset,
+            bits,
+        });
+        if bits > total_bits - offset {
+            let message = format!(
+                "Bitfield members occupy {} bits, exceeding the {} bits of the underlying type",
+                offset as u64 + bits as u64,
+                total_bits
+            );
+            return Err(syn::Error::new(brace.span.join(), message));
+        }
+        offset += bits;
+        if content.is_empty() {
+            break;
+        }
+        let _comma: syn::Token![,] = content.parse()?;
+    }
+    Ok(Type::Bitfield { ty, members })
+}
+
+/// `#[align = N]`. Alignments must be a power of two.
+fn parse_align_attribute(content: ParseStream) -> syn::Result<usize> {
+    let _eq = content.parse::<syn::Token![=]>()?;
+    let literal = content.parse::<syn::LitInt>()?;
+    let align: usize = literal.base10_parse()?;
+    if !align.is_power_of_two() {
+        return Err(syn::Error::new(
+            literal.span(),
+            "Alignment must be a power of two",
+        ));
+    }
+    Ok(align)
+}
+
+fn parse_memory_qualifier(ident: &syn::Ident) -> Option<MemoryQualifiers> {
+    match ident.to_string().as_str() {
+        "readonly" => Some(MemoryQualifiers::READONLY),
+        "writeonly" => Some(MemoryQualifiers::WRITEONLY),
+        "coherent" => Some(MemoryQualifiers::COHERENT),
+        "volatile" => Some(MemoryQualifiers::VOLATILE),
+        "restrict" => Some(MemoryQualifiers::RESTRICT),
+        _ => None,
+    }
+}
+
+fn parse_matrix_layout(ident: &syn::Ident) -> Option<MatrixLayout> {
+    if ident == "row_major" {
+        Some(MatrixLayout::RowMajor)
+    } else if ident == "column_major" {
+        Some(MatrixLayout::ColumnMajor)
+    } else {
+        None
+    }
+}
+
 impl Parse for Field {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut matrix_layout = None;
+        let mut offset = None;
+        let mut align = None;
+        while input.peek(syn::Token![#]) {
+            let _pound: syn::Token![#] = input.parse()?;
+            let content;
+            let _bracket: syn::token::Bracket = syn::bracketed!(content in input);
+            let ident = content.parse::<syn::Ident>()?;
+            if let Some(layout) = parse_matrix_layout(&ident) {
+                matrix_layout = Some(layout);
+            } else if ident == "offset" {
+                let _eq = content.parse::<syn::Token![=]>()?;
+                offset = Some(content.parse::<syn::LitInt>()?.base10_parse()?);
+            } else if ident == "align" {
+                align = Some(parse_align_attribute(&content)?);
+            } else {
+                return Err(syn::Error::new(ident.span(), "unknown attribute"));
+            }
+        }
         let ident: syn::Ident = input.parse()?;
         let _colon: syn::Token![:] = input.parse()?;
         let ty: Type = input.parse()?;
         Ok(Self {
             ident: Some(ident.to_string()),
             ty,
+            matrix_layout,
+            offset,
+            align,
         })
     }
 }
@@ -496,10 +1713,135 @@ impl Parse for DataStruct {
         }
         let content;
         let _paren: syn::token::Brace = syn::braced!(content in input);
-        let fields = content.parse_terminated(Field::parse, syn::Token![,])?;
+        let mut fields: Vec<Field> = Vec::new();
+        loop {
+            if content.is_empty() {
+                break;
+            }
+            let span = content.span();
+            let field: Field = content.parse()?;
+            if fields.iter().any(|other| other.ident == field.ident) {
+                return Err(syn::Error::new(span, "Duplicated field"));
+            }
+            fields.push(field);
+            if content.is_empty() {
+                break;
+            }
+            let _comma: syn::Token![,] = content.parse()?;
+        }
+        Ok(Self {
+            ident: name.to_string(),
+            fields,
+            align: None,
+        })
+    }
+}
+
+impl Parse for DataEnum {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let _enum = input.parse::<syn::Token![enum]>()?;
+        let name = input.parse::<syn::Ident>()?;
+        let ty = if input.peek(syn::Token![:]) {
+            let _colon: syn::Token![:] = input.parse()?;
+            let ty_span = input.span();
+            let ty: PrimitiveTypeSingle = input.parse()?;
+            if !ty.is_integer() {
+                return Err(syn::Error::new(
+                    ty_span,
+                    "Enums must be backed by an integer type",
+                ));
+            }
+            ty
+        } else {
+            PrimitiveTypeSingle::U32
+        };
+        let lookahead = input.lookahead1();
+        if !lookahead.peek(syn::token::Brace) {
+            return Err(lookahead.error());
+        }
+        let content;
+        let _paren: syn::token::Brace = syn::braced!(content in input);
+
+        let mut variants: Vec<EnumVariant> = Vec::new();
+        // `None` once the previous value was `i64::MAX`
+        let mut next_value: Option<i64> = Some(0);
+        loop {
+            if content.is_empty() {
+                break;
+            }
+            let ident = content.parse::<syn::Ident>()?;
+            let mut value_span = ident.span();
+            let value = if content.peek(syn::Token![=]) {
+                // If the value was specified, subsequent variants count up from there
+                let _eq: syn::Token![=] = content.parse()?;
+                let negative = content.parse::<Option<syn::Token![-]>>()?.is_some();
+                let literal = content.parse::<syn::LitInt>()?;
+                value_span = literal.span();
+                let value = literal.base10_parse::<i128>()?;
+                i64::try_from(if negative { -value } else { value }).map_err(|_| {
+                    syn::Error::new(
+                        value_span,
+                        "Enum value is out of range for the backing type",
+                    )
+                })?
+            } else {
+                next_value.ok_or_else(|| {
+                    syn::Error::new(
+                        value_span,
+                        "Enum value is out of range for the backing type",
+                    )
+                })?
+            };
+            if !ty.integer_range().unwrap().contains(&value) {
+                return Err(syn::Error::new(
+                    value_span,
+                    format!("Enum value {} is out of range for the backing type", value),
+                ));
+            }
+            if variants.iter().any(|variant| ident == variant.ident) {
+                return Err(syn::Error::new(ident.span(), "Duplicated enum variant"));
+            }
+            if let Some(other) = variants.iter().find(|variant| variant.value == value) {
+                return Err(syn::Error::new(
+                    value_span,
+                    format!("Enum value {} is already used by `{}`", value, other.ident),
+                ));
+            }
+            variants.push(EnumVariant {
+                ident: ident.to_string(),
+                value,
+            });
+            next_value = value.checked_add(1);
+            if content.is_empty() {
+                break;
+            }
+            let _comma: syn::Token![,] = content.parse()?;
+        }
+        Ok(Self {
+            ident: name.to_string(),
+            ty,
+            variants,
+        })
+    }
+}
+
+impl Parse for TypeAlias {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let _type = input.parse::<syn::Token![type]>()?;
+        let name = input.parse::<syn::Ident>()?;
+        let _eq: syn::Token![=] = input.parse()?;
+        let span = input.span();
+        let ty: Type = input.parse()?;
+        if matches!(ty, Type::Bitfield { .. }) {
+            return Err(syn::Error::new(
+                span,
+                "Bitfields must be declared on a struct field",
+            ));
+        }
+        let _semicolon: syn::Token![;] = input.parse()?;
         Ok(Self {
             ident: name.to_string(),
-            fields: fields.into_iter().collect(),
+            ty,
         })
     }
 }
@@ -512,3 +1854,147 @@ impl TryFrom<&str> for PlayoutModule {
         Ok(tokens)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        PlayoutModule::try_from(source).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn alias_cycle() {
+        for source in [
+            "type A = B; type B = A;",
+            "type A = [A; 2]; struct S { a: A }",
+            "type A = [B]; type B = Ptr<A>;",
+        ] {
+            assert_eq!(error(source), "Type alias refers to itself");
+        }
+    }
+
+    #[test]
+    fn enum_values() {
+        let module = PlayoutModule::try_from(
+            "enum E: i8 { A = -128, B, C = -1, D } enum F: i64 { A = -9223372036854775808 }",
+        )
+        .unwrap();
+        let values: Vec<i64> = module.data_enums["E"]
+            .variants
+            .iter()
+            .map(|variant| variant.value)
+            .collect();
+        assert_eq!(values, [-128, -127, -1, 0]);
+        assert_eq!(module.data_enums["F"].variants[0].value, i64::MIN);
+        assert_eq!(
+            error("enum E: i64 { A = 9223372036854775807, B }"),
+            "Enum value is out of range for the backing type"
+        );
+        assert_eq!(
+            error("enum E: u32 { A = -1 }"),
+            "Enum value -1 is out of range for the backing type"
+        );
+        assert_eq!(
+            error("enum E { A = 1, B = 0, C }"),
+            "Enum value 1 is already used by `A`"
+        );
+    }
+
+    #[test]
+    fn bitfields() {
+        assert_eq!(
+            error("struct S { p: u32 { a: 4000000000, b: 4000000000 } }"),
+            "Bitfield members occupy 4000000000 bits, exceeding the 32 bits of the underlying type"
+        );
+        assert_eq!(
+            error("struct S { p: u8 { a: 4, b: 5 } }"),
+            "Bitfield members occupy 9 bits, exceeding the 8 bits of the underlying type"
+        );
+        assert_eq!(
+            error("struct S { p: [u32 { a: 4 }; 4] }"),
+            "Bitfields must be declared on a struct field"
+        );
+        assert_eq!(
+            error("struct S { p: u32 { r#type: 4 } }"),
+            "Bitfield members can't be named after keywords"
+        );
+    }
+
+    #[test]
+    fn push_descriptor_count() {
+        assert_eq!(
+            error(
+                "#[set(push_descriptor)] struct S {
+                    #![stage(COMPUTE)]
+                    a: [SampledImage; 4294967295],
+                    b: SampledImage,
+                }"
+            ),
+            format!(
+                "Push descriptor sets can have at most {} descriptors, but this one has 4294967296",
+                SetLayout::MAX_PUSH_DESCRIPTORS
+            )
+        );
+    }
+
+    #[test]
+    fn push_descriptor_mutable() {
+        assert_eq!(
+            error(
+                "#[set(push_descriptor)] struct S {
+                    #![stage(COMPUTE)]
+                    heap: Mutable<SampledImage | StorageBuffer<[u32]>>,
+                }"
+            ),
+            "Push descriptor sets can't have mutable descriptors, but `heap` is one"
+        );
+    }
+
+    #[test]
+    fn input_attachment() {
+        let module = PlayoutModule::try_from(
+            "#[set] struct S {
+                #![stage(FRAGMENT)]
+                a: InputAttachment<index = 0>,
+                b: InputAttachment<index = 1, u32, Dim2MS>,
+            }",
+        )
+        .unwrap();
+        let types: Vec<_> = module.descriptor_sets[0]
+            .bindings
+            .iter()
+            .map(|binding| match binding.descriptor_type {
+                DescriptorType::InputAttachment {
+                    index,
+                    component,
+                    multisampled,
+                } => (index, component, multisampled),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            types,
+            [
+                (0, ImageComponentType::Float, false),
+                (1, ImageComponentType::UInt, true)
+            ]
+        );
+        assert_eq!(
+            error("#[set] struct S { #![stage(FRAGMENT)] a: InputAttachment<index = 0, Dim3> }"),
+            "Input attachments are Dim2 or Dim2MS"
+        );
+    }
+
+    #[test]
+    fn struct_cycle() {
+        for source in [
+            "struct S { a: f32, s: S }",
+            "struct S { a: [T; 2] } struct T { s: S }",
+            "type A = [S]; struct S { a: A }",
+        ] {
+            assert_eq!(error(source), "Struct `S` contains itself");
+        }
+        assert!(PlayoutModule::try_from("struct S { a: f32, next: Ptr<S> }").is_ok());
+    }
+}
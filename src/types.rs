@@ -23,20 +23,163 @@ bitflags::bitflags! {
 }
 
 pub enum DescriptorType {
-    Sampler,
-    StorageImage { format: ImageFormat },
-    SampledImage,
-    CombinedImageSampler,
-    UniformBuffer { ty: Type },
-    InlineUniformBlock { ty: Type },
-    StorageBuffer { ty: Type },
+    Sampler {
+        shadow: bool,
+    },
+    StorageImage {
+        /// `None` for images declared with `Unknown` or a component type, which require
+        /// reading or writing without a format.
+        format: Option<ImageFormat>,
+        component: ImageComponentType,
+        dimension: ImageDimension,
+    },
+    SampledImage {
+        dimension: ImageDimension,
+        component: ImageComponentType,
+    },
+    CombinedImageSampler {
+        dimension: ImageDimension,
+        component: ImageComponentType,
+        shadow: bool,
+    },
+    UniformTexelBuffer {
+        format: ImageFormat,
+    },
+    StorageTexelBuffer {
+        format: ImageFormat,
+    },
+    UniformBuffer {
+        ty: Type,
+    },
+    StorageBuffer {
+        ty: Type,
+    },
+    UniformBufferDynamic {
+        ty: Type,
+    },
+    StorageBufferDynamic {
+        ty: Type,
+    },
+    /// `InputAttachment<index = 0, u32, Dim2MS>`, declared as `usubpassInputMS`.
+    InputAttachment {
+        index: u32,
+        component: ImageComponentType,
+        multisampled: bool,
+    },
+    InlineUniformBlock {
+        ty: Type,
+    },
     AccelerationStructure,
+    /// `Mutable<SampledImage | StorageImage<RGBA8_UNorm>>`, holding a descriptor of any of
+    /// its member types.
+    Mutable {
+        types: Vec<DescriptorType>,
+    },
+}
+
+/// Maps each `DescriptorType` variant to its `vk::DescriptorType` constant.
+macro_rules! descriptor_type_vk {
+    ($($name:ident => $vk:ident;)*) => {
+        impl DescriptorType {
+            /// Name of the `vk::DescriptorType` constant, as in `STORAGE_BUFFER`.
+            pub fn vk_descriptor_type_name(&self) -> &'static str {
+                match self {
+                    $(Self::$name { .. } => stringify!($vk),)*
+                }
+            }
+            #[cfg(feature = "ash")]
+            pub fn to_vk(&self) -> ash::vk::DescriptorType {
+                match self {
+                    $(Self::$name { .. } => ash::vk::DescriptorType::$vk,)*
+                }
+            }
+        }
+    };
+}
+
+descriptor_type_vk! {
+    Sampler => SAMPLER;
+    CombinedImageSampler => COMBINED_IMAGE_SAMPLER;
+    SampledImage => SAMPLED_IMAGE;
+    StorageImage => STORAGE_IMAGE;
+    UniformTexelBuffer => UNIFORM_TEXEL_BUFFER;
+    StorageTexelBuffer => STORAGE_TEXEL_BUFFER;
+    UniformBuffer => UNIFORM_BUFFER;
+    StorageBuffer => STORAGE_BUFFER;
+    UniformBufferDynamic => UNIFORM_BUFFER_DYNAMIC;
+    StorageBufferDynamic => STORAGE_BUFFER_DYNAMIC;
+    InputAttachment => INPUT_ATTACHMENT;
+    InlineUniformBlock => INLINE_UNIFORM_BLOCK;
+    AccelerationStructure => ACCELERATION_STRUCTURE_KHR;
+    Mutable => MUTABLE_EXT;
 }
 
 impl DescriptorType {
     pub fn same_type_as(&self, other: &Self) -> bool {
         std::mem::discriminant(self) == std::mem::discriminant(other)
     }
+    /// Storage buffers, storage images and storage texel buffers can be written by shaders,
+    /// and accept memory qualifiers.
+    pub fn is_writable(&self) -> bool {
+        match self {
+            DescriptorType::StorageImage { .. }
+            | DescriptorType::StorageTexelBuffer { .. }
+            | DescriptorType::StorageBuffer { .. }
+            | DescriptorType::StorageBufferDynamic { .. } => true,
+            DescriptorType::Mutable { types } => types.iter().any(DescriptorType::is_writable),
+            _ => false,
+        }
+    }
+    /// The descriptor types a binding can hold: the members of `Mutable`, or the type itself.
+    pub fn members(&self) -> &[DescriptorType] {
+        match self {
+            DescriptorType::Mutable { types } => types,
+            _ => std::slice::from_ref(self),
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            DescriptorType::Sampler { .. } => "Sampler",
+            DescriptorType::StorageImage { .. } => "StorageImage",
+            DescriptorType::SampledImage { .. } => "SampledImage",
+            DescriptorType::CombinedImageSampler { .. } => "CombinedImageSampler",
+            DescriptorType::UniformTexelBuffer { .. } => "UniformTexelBuffer",
+            DescriptorType::StorageTexelBuffer { .. } => "StorageTexelBuffer",
+            DescriptorType::UniformBuffer { .. } => "UniformBuffer",
+            DescriptorType::StorageBuffer { .. } => "StorageBuffer",
+            DescriptorType::UniformBufferDynamic { .. } => "UniformBufferDynamic",
+            DescriptorType::StorageBufferDynamic { .. } => "StorageBufferDynamic",
+            DescriptorType::InputAttachment { .. } => "InputAttachment",
+            DescriptorType::InlineUniformBlock { .. } => "InlineUniformBlock",
+            DescriptorType::AccelerationStructure => "AccelerationStructure",
+            DescriptorType::Mutable { .. } => "Mutable",
+        }
+    }
+    /// The `wgpu::TextureSampleType` of sampled images and combined image samplers.
+    #[cfg(feature = "wgpu")]
+    pub fn to_wgpu_sample_type(&self) -> Option<wgpu_types::TextureSampleType> {
+        match self {
+            DescriptorType::CombinedImageSampler { shadow: true, .. } => {
+                Some(wgpu_types::TextureSampleType::Depth)
+            }
+            DescriptorType::SampledImage { component, .. }
+            | DescriptorType::CombinedImageSampler { component, .. } => {
+                Some(component.to_wgpu(true))
+            }
+            _ => None,
+        }
+    }
+    /// The type of the data in uniform, storage and inline uniform blocks.
+    pub fn block_type(&self) -> Option<&Type> {
+        match self {
+            DescriptorType::UniformBuffer { ty }
+            | DescriptorType::StorageBuffer { ty }
+            | DescriptorType::UniformBufferDynamic { ty }
+            | DescriptorType::StorageBufferDynamic { ty }
+            | DescriptorType::InlineUniformBlock { ty } => Some(ty),
+            _ => None,
+        }
+    }
 }
 
 impl Debug for DescriptorType {
@@ -45,11 +188,237 @@ impl Debug for DescriptorType {
     }
 }
 
+/// `sampler LinearClamp { mag_filter: Linear, address_mode: ClampToEdge }`, bound to `Sampler`
+/// and `CombinedImageSampler` bindings with `#[immutable(LinearClamp)]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImmutableSampler {
+    pub ident: String,
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub mipmap_mode: SamplerMipmapMode,
+    pub address_mode_u: SamplerAddressMode,
+    pub address_mode_v: SamplerAddressMode,
+    pub address_mode_w: SamplerAddressMode,
+    pub mip_lod_bias: f32,
+    pub max_anisotropy: Option<f32>,
+    pub compare_op: Option<CompareOp>,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub border_color: BorderColor,
+    pub unnormalized_coordinates: bool,
+}
+
+impl ImmutableSampler {
+    /// `VK_LOD_CLAMP_NONE`
+    pub const LOD_CLAMP_NONE: f32 = 1000.0;
+
+    pub fn new(ident: String) -> Self {
+        Self {
+            ident,
+            mag_filter: Filter::default(),
+            min_filter: Filter::default(),
+            mipmap_mode: SamplerMipmapMode::default(),
+            address_mode_u: SamplerAddressMode::default(),
+            address_mode_v: SamplerAddressMode::default(),
+            address_mode_w: SamplerAddressMode::default(),
+            mip_lod_bias: 0.0,
+            max_anisotropy: None,
+            compare_op: None,
+            min_lod: 0.0,
+            max_lod: Self::LOD_CLAMP_NONE,
+            border_color: BorderColor::default(),
+            unnormalized_coordinates: false,
+        }
+    }
+}
+
+/// The value of a `vk::SamplerCreateInfo` field. Enums are the name of the `vk` type and the raw
+/// value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplerFieldValue {
+    Enum(&'static str, i32),
+    Float(f32),
+    Bool(bool),
+}
+
+/// Types of the `ImmutableSampler` fields passed to `vk::SamplerCreateInfo`.
+pub(crate) trait SamplerField: Copy {
+    #[cfg(feature = "ash")]
+    type Vk;
+    fn value(self) -> SamplerFieldValue;
+    #[cfg(feature = "ash")]
+    fn to_vk(self) -> Self::Vk;
+}
+
+impl SamplerField for f32 {
+    #[cfg(feature = "ash")]
+    type Vk = f32;
+    fn value(self) -> SamplerFieldValue {
+        SamplerFieldValue::Float(self)
+    }
+    #[cfg(feature = "ash")]
+    fn to_vk(self) -> f32 {
+        self
+    }
+}
+
+impl SamplerField for bool {
+    #[cfg(feature = "ash")]
+    type Vk = bool;
+    fn value(self) -> SamplerFieldValue {
+        SamplerFieldValue::Bool(self)
+    }
+    #[cfg(feature = "ash")]
+    fn to_vk(self) -> bool {
+        self
+    }
+}
+
+/// Declares how `ImmutableSampler` maps to `vk::SamplerCreateInfo`, from a table of the fields
+/// always set, then the optional fields and the flag enabling them. Field names are the names of
+/// the `vk::SamplerCreateInfo` builder methods.
+macro_rules! sampler_create_info {
+    ($($field:ident,)* ; $($optional:ident if $enable:ident,)*) => {
+        impl ImmutableSampler {
+            /// The `vk::SamplerCreateInfo` builder methods `to_vk` calls, with their arguments.
+            pub fn vk_fields(&self) -> Vec<(&'static str, SamplerFieldValue)> {
+                let mut fields = vec![$((stringify!($field), self.$field.value()),)*];
+                $(
+                    if let Some(value) = self.$optional {
+                        fields.push((stringify!($enable), SamplerFieldValue::Bool(true)));
+                        fields.push((stringify!($optional), value.value()));
+                    }
+                )*
+                fields
+            }
+            #[cfg(feature = "ash")]
+            pub fn to_vk(&self) -> ash::vk::SamplerCreateInfo<'static> {
+                let mut info = ash::vk::SamplerCreateInfo::default()
+                    $(.$field(self.$field.to_vk()))*;
+                $(
+                    if let Some(value) = self.$optional {
+                        info = info.$enable(true).$optional(value.to_vk());
+                    }
+                )*
+                info
+            }
+        }
+    };
+}
+
+sampler_create_info! {
+    mag_filter,
+    min_filter,
+    mipmap_mode,
+    address_mode_u,
+    address_mode_v,
+    address_mode_w,
+    mip_lod_bias,
+    min_lod,
+    max_lod,
+    border_color,
+    unnormalized_coordinates,
+    ;
+    max_anisotropy if anisotropy_enable,
+    compare_op if compare_enable,
+}
+
+/// Enums of sampler parameters, with the values of their Vulkan counterparts.
+macro_rules! sampler_enums {
+    ($($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident = $value:literal,)* })*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+            pub enum $name {
+                $($(#[$variant_meta])* $variant = $value,)*
+            }
+
+            impl $name {
+                pub fn from_name(name: &str) -> Option<Self> {
+                    match name {
+                        $(stringify!($variant) => Some(Self::$variant),)*
+                        _ => None,
+                    }
+                }
+            }
+
+            impl SamplerField for $name {
+                #[cfg(feature = "ash")]
+                type Vk = ash::vk::$name;
+                fn value(self) -> SamplerFieldValue {
+                    SamplerFieldValue::Enum(stringify!($name), self as i32)
+                }
+                #[cfg(feature = "ash")]
+                fn to_vk(self) -> Self::Vk {
+                    ash::vk::$name::from_raw(self as i32)
+                }
+            }
+        )*
+    };
+}
+
+sampler_enums! {
+    Filter {
+        #[default]
+        Nearest = 0,
+        Linear = 1,
+    }
+    SamplerMipmapMode {
+        #[default]
+        Nearest = 0,
+        Linear = 1,
+    }
+    SamplerAddressMode {
+        #[default]
+        Repeat = 0,
+        MirroredRepeat = 1,
+        ClampToEdge = 2,
+        ClampToBorder = 3,
+        MirrorClampToEdge = 4,
+    }
+    CompareOp {
+        #[default]
+        Never = 0,
+        Less = 1,
+        Equal = 2,
+        LessOrEqual = 3,
+        Greater = 4,
+        NotEqual = 5,
+        GreaterOrEqual = 6,
+        Always = 7,
+    }
+    BorderColor {
+        #[default]
+        FloatTransparentBlack = 0,
+        IntTransparentBlack = 1,
+        FloatOpaqueBlack = 2,
+        IntOpaqueBlack = 3,
+        FloatOpaqueWhite = 4,
+        IntOpaqueWhite = 5,
+    }
+}
+
 #[derive(Debug)]
 pub struct SetLayout {
     pub name: String,
     pub set: u32,
     pub bindings: Vec<Binding>,
+    /// Set by `#[set(push_descriptor)]`. The set is written with `vkCmdPushDescriptorSetKHR`
+    /// instead of being allocated.
+    pub push_descriptor: bool,
+}
+
+impl SetLayout {
+    /// Guaranteed minimum of `maxPushDescriptors`.
+    pub const MAX_PUSH_DESCRIPTORS: u32 = 32;
+
+    /// Sets with update-after-bind bindings must be allocated from pools created with
+    /// `UPDATE_AFTER_BIND`.
+    pub fn update_after_bind(&self) -> bool {
+        self.bindings
+            .iter()
+            .any(|binding| binding.flags.contains(BindingFlags::UPDATE_AFTER_BIND))
+    }
 }
 
 #[derive(Default)]
@@ -71,76 +440,310 @@ pub struct Binding {
     pub descriptor_type: DescriptorType,
     pub descriptor_count: u32, // ALso needs: binding id, immutable sampler, shader stage flags
     pub layout: Option<String>,
+    pub memory: MemoryQualifiers,
+    pub flags: BindingFlags,
+    /// Name of the sampler set by `#[immutable(LinearClamp)]`, used for every array element.
+    pub immutable_sampler: Option<String>,
+    pub aliases: Vec<DescriptorAlias>,
 }
 
-#[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy)]
-pub enum ImageFormat {
-    RGBA32_Float,
-    RGBA16_Float,
-    RG32_Float,
-    RG16_Float,
-    R11G11B10_Float,
-    R32_Float,
-    R16_Float,
-
-    RGBA16_UNorm,
-    RGB10A2_UNorm,
-    RGBA8_UNorm,
-    RG16_UNorm,
-    RG8_UNorm,
-    R16_UNorm,
-    R8_UNorm,
-
-    RGBA16_SNorm,
-    RBGA8_SNorm,
-    RG16_SNorm,
-    RG8_SNorm,
-    R16_SNorm,
-    R8_SNorm,
-
-    RGBA32_SInt,
-    RGBA16_SInt,
-    RGBA8_SInt,
-    RG32_SInt,
-    RG16_SInt,
-    RG8_SInt,
-    R32_SInt,
-    R16_SInt,
-    R8_SInt,
-
-    RGBA32_UInt,
-    RGBA16_UInt,
-    RGB10A2_UInt,
-    RGBA8_UInt,
-    RG32_UInt,
-    RG16_UInt,
-    RG8_UInt,
-    R32_UInt,
-    R16_UInt,
-    R8_UInt,
+/// `#[alias(voxels_u8: StorageBuffer<[u8]>)]`, another shader-side view of a binding.
+#[derive(Debug)]
+pub struct DescriptorAlias {
+    pub ident: String,
+    pub descriptor_type: DescriptorType,
 }
 
-impl ImageFormat {
-    pub fn data_mode(&self) -> ImageFormatDataMode {
-        use ImageFormat::*;
+bitflags::bitflags! {
+    /// `VkDescriptorBindingFlags`, set by `#[update_after_bind]`, `#[update_unused_while_pending]`,
+    /// `#[partially_bound]` and `#[variable_count]`. Unsized descriptor arrays have a variable
+    /// descriptor count.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct BindingFlags: u32 {
+        const UPDATE_AFTER_BIND = 0x1;
+        const UPDATE_UNUSED_WHILE_PENDING = 0x2;
+        const PARTIALLY_BOUND = 0x4;
+        const VARIABLE_DESCRIPTOR_COUNT = 0x8;
+    }
+}
+
+bitflags::bitflags! {
+    /// Set by `#[readonly]`, `#[writeonly]`, `#[coherent]`, `#[volatile]` and `#[restrict]`
+    /// on storage buffers, storage images and storage texel buffers.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct MemoryQualifiers: u32 {
+        const READONLY = 0x1;
+        const WRITEONLY = 0x2;
+        const COHERENT = 0x4;
+        const VOLATILE = 0x8;
+        const RESTRICT = 0x10;
+    }
+}
+
+bitflags::bitflags! {
+    /// How shaders may access the resources bound to a binding.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DescriptorAccess: u32 {
+        const READ = 0x1;
+        const WRITE = 0x2;
+    }
+}
+
+impl Binding {
+    /// Every type the binding is declared with in shaders: the members of mutable descriptors,
+    /// and the types of aliases.
+    pub fn declared_types(&self) -> impl Iterator<Item = &DescriptorType> {
+        self.descriptor_type.members().iter().chain(
+            self.aliases
+                .iter()
+                .flat_map(|alias| alias.descriptor_type.members()),
+        )
+    }
+    /// Storage resources are read and written unless qualified with `#[readonly]` or
+    /// `#[writeonly]`. Everything else is only read.
+    pub fn access(&self) -> DescriptorAccess {
+        if !self.descriptor_type.is_writable() {
+            return DescriptorAccess::READ;
+        }
+        if self.memory.contains(MemoryQualifiers::READONLY) {
+            DescriptorAccess::READ
+        } else if self.memory.contains(MemoryQualifiers::WRITEONLY) {
+            DescriptorAccess::WRITE
+        } else {
+            DescriptorAccess::READ | DescriptorAccess::WRITE
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageDimension {
+    Dim1,
+    #[default]
+    Dim2,
+    Dim3,
+    Cube,
+    Dim1Array,
+    Dim2Array,
+    CubeArray,
+    Dim2MS,
+    Dim2MSArray,
+}
+
+impl ImageDimension {
+    pub fn from_name(name: &str) -> Option<Self> {
+        use ImageDimension::*;
+        Some(match name {
+            "Dim1" => Dim1,
+            "Dim2" => Dim2,
+            "Dim3" => Dim3,
+            "Cube" => Cube,
+            "Dim1Array" => Dim1Array,
+            "Dim2Array" => Dim2Array,
+            "CubeArray" => CubeArray,
+            "Dim2MS" => Dim2MS,
+            "Dim2MSArray" => Dim2MSArray,
+            _ => return None,
+        })
+    }
+    pub fn is_arrayed(&self) -> bool {
+        use ImageDimension::*;
+        matches!(self, Dim1Array | Dim2Array | CubeArray | Dim2MSArray)
+    }
+    pub fn is_multisampled(&self) -> bool {
+        matches!(self, ImageDimension::Dim2MS | ImageDimension::Dim2MSArray)
+    }
+    /// Depth comparison is not available on 3D and multisampled images.
+    pub fn supports_shadow(&self) -> bool {
+        !self.is_multisampled() && *self != ImageDimension::Dim3
+    }
+}
+
+/// Type of the components read from or written to an image, as in `texture2D`, `itexture2D`
+/// and `utexture2D`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageComponentType {
+    #[default]
+    Float,
+    SInt,
+    UInt,
+}
+
+impl ImageComponentType {
+    pub fn from_primitive(ty: PrimitiveTypeSingle) -> Option<Self> {
+        match ty {
+            PrimitiveTypeSingle::F32 => Some(Self::Float),
+            PrimitiveTypeSingle::I32 => Some(Self::SInt),
+            PrimitiveTypeSingle::U32 => Some(Self::UInt),
+            _ => None,
+        }
+    }
+    #[cfg(feature = "wgpu")]
+    pub fn to_wgpu(&self, filterable: bool) -> wgpu_types::TextureSampleType {
         match self {
-            RGBA32_Float | RGBA16_Float | RG32_Float | RG16_Float | R11G11B10_Float | R32_Float
-            | R16_Float => ImageFormatDataMode::Float,
+            Self::Float => wgpu_types::TextureSampleType::Float { filterable },
+            Self::SInt => wgpu_types::TextureSampleType::Sint,
+            Self::UInt => wgpu_types::TextureSampleType::Uint,
+        }
+    }
+}
 
-            RGBA16_UNorm | RGB10A2_UNorm | RGBA8_UNorm | RG16_UNorm | RG8_UNorm | R16_UNorm
-            | R8_UNorm => ImageFormatDataMode::UNorm,
+/// `Some` of an optional table entry.
+macro_rules! optional {
+    () => {
+        None
+    };
+    ($($value:tt)+) => {
+        Some($($value)+)
+    };
+}
 
-            RGBA16_SNorm | RBGA8_SNorm | RG16_SNorm | RG8_SNorm | R16_SNorm | R8_SNorm => {
-                ImageFormatDataMode::SNorm
-            }
+/// Declares `ImageFormat` from a table of
+/// `Name => vk::Format, [wgpu::TextureFormat], GLSL format qualifier, data mode, components, bytes`.
+/// Formats without a GLSL format qualifier can't be used for storage images.
+macro_rules! image_formats {
+    ($($name:ident => $vk:ident, [$($wgpu:ident)?], $glsl:expr, $mode:ident, $components:literal, $bytes:literal;)*) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum ImageFormat {
+            $($name,)*
+        }
 
-            RGBA32_SInt | RGBA16_SInt | RGBA8_SInt | RG32_SInt | RG16_SInt | RG8_SInt
-            | R32_SInt | R16_SInt | R8_SInt => ImageFormatDataMode::SInt,
+        impl ImageFormat {
+            pub const ALL: &'static [ImageFormat] = &[$(ImageFormat::$name,)*];
 
-            RGBA32_UInt | RGBA16_UInt | RGB10A2_UInt | RGBA8_UInt | RG32_UInt | RG16_UInt
-            | RG8_UInt | R32_UInt | R16_UInt | R8_UInt => ImageFormatDataMode::UInt,
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($name),)*
+                }
+            }
+            /// Name of the `vk::Format` constant, as in `R16G16B16A16_SFLOAT`.
+            pub fn vk_format_name(&self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($vk),)*
+                }
+            }
+            #[cfg(feature = "ash")]
+            pub fn to_vk(&self) -> ash::vk::Format {
+                match self {
+                    $(Self::$name => ash::vk::Format::$vk,)*
+                }
+            }
+            /// Name of the `wgpu::TextureFormat` variant, as in `Rgba16Float`.
+            pub fn wgpu_format_name(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$name => optional!($(stringify!($wgpu))?),)*
+                }
+            }
+            /// `None` for formats wgpu doesn't support.
+            #[cfg(feature = "wgpu")]
+            pub fn to_wgpu(&self) -> Option<wgpu_types::TextureFormat> {
+                match self {
+                    $(Self::$name => optional!($(wgpu_types::TextureFormat::$wgpu)?),)*
+                }
+            }
+            /// The GLSL format layout qualifier, as in `rgba16f`.
+            pub fn glsl_format_name(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$name => $glsl,)*
+                }
+            }
+            pub fn data_mode(&self) -> ImageFormatDataMode {
+                match self {
+                    $(Self::$name => ImageFormatDataMode::$mode,)*
+                }
+            }
+            pub fn component_count(&self) -> u32 {
+                match self {
+                    $(Self::$name => $components,)*
+                }
+            }
+            pub fn bytes_per_texel(&self) -> u32 {
+                match self {
+                    $(Self::$name => $bytes,)*
+                }
+            }
         }
+    };
+}
+
+image_formats! {
+    RGBA32_Float => R32G32B32A32_SFLOAT, [Rgba32Float], Some("rgba32f"), Float, 4, 16;
+    RGBA16_Float => R16G16B16A16_SFLOAT, [Rgba16Float], Some("rgba16f"), Float, 4, 8;
+    RG32_Float => R32G32_SFLOAT, [Rg32Float], Some("rg32f"), Float, 2, 8;
+    RG16_Float => R16G16_SFLOAT, [Rg16Float], Some("rg16f"), Float, 2, 4;
+    R11G11B10_Float => B10G11R11_UFLOAT_PACK32, [Rg11b10Ufloat], Some("r11f_g11f_b10f"), Float, 3, 4;
+    RGB9E5_Float => E5B9G9R9_UFLOAT_PACK32, [Rgb9e5Ufloat], None, Float, 3, 4;
+    R32_Float => R32_SFLOAT, [R32Float], Some("r32f"), Float, 1, 4;
+    R16_Float => R16_SFLOAT, [R16Float], Some("r16f"), Float, 1, 2;
+
+    RGBA16_UNorm => R16G16B16A16_UNORM, [Rgba16Unorm], Some("rgba16"), UNorm, 4, 8;
+    RGB10A2_UNorm => A2B10G10R10_UNORM_PACK32, [Rgb10a2Unorm], Some("rgb10_a2"), UNorm, 4, 4;
+    RGBA8_UNorm => R8G8B8A8_UNORM, [Rgba8Unorm], Some("rgba8"), UNorm, 4, 4;
+    RGBA8_sRGB => R8G8B8A8_SRGB, [Rgba8UnormSrgb], None, UNorm, 4, 4;
+    BGRA8_UNorm => B8G8R8A8_UNORM, [Bgra8Unorm], None, UNorm, 4, 4;
+    BGRA8_sRGB => B8G8R8A8_SRGB, [Bgra8UnormSrgb], None, UNorm, 4, 4;
+    RG16_UNorm => R16G16_UNORM, [Rg16Unorm], Some("rg16"), UNorm, 2, 4;
+    RG8_UNorm => R8G8_UNORM, [Rg8Unorm], Some("rg8"), UNorm, 2, 2;
+    R16_UNorm => R16_UNORM, [R16Unorm], Some("r16"), UNorm, 1, 2;
+    R8_UNorm => R8_UNORM, [R8Unorm], Some("r8"), UNorm, 1, 1;
+
+    RGBA16_SNorm => R16G16B16A16_SNORM, [Rgba16Snorm], Some("rgba16_snorm"), SNorm, 4, 8;
+    RGBA8_SNorm => R8G8B8A8_SNORM, [Rgba8Snorm], Some("rgba8_snorm"), SNorm, 4, 4;
+    RG16_SNorm => R16G16_SNORM, [Rg16Snorm], Some("rg16_snorm"), SNorm, 2, 4;
+    RG8_SNorm => R8G8_SNORM, [Rg8Snorm], Some("rg8_snorm"), SNorm, 2, 2;
+    R16_SNorm => R16_SNORM, [R16Snorm], Some("r16_snorm"), SNorm, 1, 2;
+    R8_SNorm => R8_SNORM, [R8Snorm], Some("r8_snorm"), SNorm, 1, 1;
+
+    RGBA32_SInt => R32G32B32A32_SINT, [Rgba32Sint], Some("rgba32i"), SInt, 4, 16;
+    RGBA16_SInt => R16G16B16A16_SINT, [Rgba16Sint], Some("rgba16i"), SInt, 4, 8;
+    RGBA8_SInt => R8G8B8A8_SINT, [Rgba8Sint], Some("rgba8i"), SInt, 4, 4;
+    RG32_SInt => R32G32_SINT, [Rg32Sint], Some("rg32i"), SInt, 2, 8;
+    RG16_SInt => R16G16_SINT, [Rg16Sint], Some("rg16i"), SInt, 2, 4;
+    RG8_SInt => R8G8_SINT, [Rg8Sint], Some("rg8i"), SInt, 2, 2;
+    R64_SInt => R64_SINT, [], Some("r64i"), SInt, 1, 8;
+    R32_SInt => R32_SINT, [R32Sint], Some("r32i"), SInt, 1, 4;
+    R16_SInt => R16_SINT, [R16Sint], Some("r16i"), SInt, 1, 2;
+    R8_SInt => R8_SINT, [R8Sint], Some("r8i"), SInt, 1, 1;
+
+    RGBA32_UInt => R32G32B32A32_UINT, [Rgba32Uint], Some("rgba32ui"), UInt, 4, 16;
+    RGBA16_UInt => R16G16B16A16_UINT, [Rgba16Uint], Some("rgba16ui"), UInt, 4, 8;
+    RGB10A2_UInt => A2B10G10R10_UINT_PACK32, [Rgb10a2Uint], Some("rgb10_a2ui"), UInt, 4, 4;
+    RGBA8_UInt => R8G8B8A8_UINT, [Rgba8Uint], Some("rgba8ui"), UInt, 4, 4;
+    RG32_UInt => R32G32_UINT, [Rg32Uint], Some("rg32ui"), UInt, 2, 8;
+    RG16_UInt => R16G16_UINT, [Rg16Uint], Some("rg16ui"), UInt, 2, 4;
+    RG8_UInt => R8G8_UINT, [Rg8Uint], Some("rg8ui"), UInt, 2, 2;
+    R64_UInt => R64_UINT, [R64Uint], Some("r64ui"), UInt, 1, 8;
+    R32_UInt => R32_UINT, [R32Uint], Some("r32ui"), UInt, 1, 4;
+    R16_UInt => R16_UINT, [R16Uint], Some("r16ui"), UInt, 1, 2;
+    R8_UInt => R8_UINT, [R8Uint], Some("r8ui"), UInt, 1, 1;
+}
+
+impl ImageFormat {
+    pub fn supports_storage(&self) -> bool {
+        self.glsl_format_name().is_some()
+    }
+    pub fn is_srgb(&self) -> bool {
+        matches!(self, ImageFormat::RGBA8_sRGB | ImageFormat::BGRA8_sRGB)
+    }
+    /// The `wgpu::TextureSampleType` of images with this format. 32-bit float formats can't be
+    /// filtered without `wgpu::Features::FLOAT32_FILTERABLE`.
+    #[cfg(feature = "wgpu")]
+    pub fn to_wgpu_sample_type(&self) -> wgpu_types::TextureSampleType {
+        let filterable = !matches!(
+            self,
+            ImageFormat::RGBA32_Float | ImageFormat::RG32_Float | ImageFormat::R32_Float
+        );
+        self.data_mode().component_type().to_wgpu(filterable)
+    }
+    /// 64-bit integer formats, accessed through `i64image` and `u64image` types.
+    pub fn is_64_bit(&self) -> bool {
+        matches!(self, ImageFormat::R64_SInt | ImageFormat::R64_UInt)
     }
 }
 
@@ -153,51 +756,134 @@ pub enum ImageFormatDataMode {
     UInt,
 }
 
+impl ImageFormatDataMode {
+    pub fn component_type(&self) -> ImageComponentType {
+        match self {
+            ImageFormatDataMode::Float
+            | ImageFormatDataMode::UNorm
+            | ImageFormatDataMode::SNorm => ImageComponentType::Float,
+            ImageFormatDataMode::SInt => ImageComponentType::SInt,
+            ImageFormatDataMode::UInt => ImageComponentType::UInt,
+        }
+    }
+}
+
 pub struct DataStruct {
     pub ident: String,
     pub fields: Vec<Field>,
+    /// Minimum alignment set by `#[align = N]`
+    pub align: Option<usize>,
 }
 
-impl DataStruct {
-    pub fn layout(&self, module: &PlayoutModule) -> std::alloc::Layout {
-        let mut size = 0;
-        let mut align = 0;
-        for field in self.fields.iter() {
-            let layout = field.ty.layout(module);
-            size += layout.size();
-            align = align.max(layout.align());
-        }
-        std::alloc::Layout::from_size_align(size, align).unwrap()
-    }
+/// An enum declared in the playout file. Lays out as its underlying integer type.
+pub struct DataEnum {
+    pub ident: String,
+    pub ty: PrimitiveTypeSingle,
+    pub variants: Vec<EnumVariant>,
+}
+
+pub struct EnumVariant {
+    pub ident: String,
+    pub value: i64,
+}
+
+/// `type Radiance = Vec3<f16>;`
+pub struct TypeAlias {
+    pub ident: String,
+    pub ty: Type,
 }
 
 pub struct Field {
     pub ident: Option<String>,
     pub ty: Type,
+    /// Set by `#[row_major]` or `#[column_major]` on the field or the enclosing struct.
+    pub matrix_layout: Option<MatrixLayout>,
+    /// Set by `#[offset = N]`
+    pub offset: Option<usize>,
+    /// Minimum alignment set by `#[align = N]`
+    pub align: Option<usize>,
+}
+
+/// Storage order of matrices in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatrixLayout {
+    #[default]
+    ColumnMajor,
+    RowMajor,
 }
 
 pub enum Type {
-    Array { ty: Box<Type>, size: usize },
+    Array {
+        ty: Box<Type>,
+        size: usize,
+    },
     Primitive(PrimitiveType),
     Path(String),
-    Slice { ty: Box<Type> },
+    Slice {
+        ty: Box<Type>,
+    },
+    /// Several small unsigned integers packed into a single integer of type `ty`.
+    Bitfield {
+        ty: PrimitiveTypeSingle,
+        members: Vec<BitfieldMember>,
+    },
+    /// A `VkDeviceAddress` pointing to a `ty`, or to an array of them when `ty` is a slice.
+    Ptr {
+        ty: Box<Type>,
+    },
     //Path, for nested structs
 }
 
+pub struct BitfieldMember {
+    pub ident: String,
+    /// Offset of the least significant bit
+    pub offset: u32,
+    pub bits: u32,
+}
+
+impl BitfieldMember {
+    pub fn mask(&self) -> u64 {
+        (1_u64 << self.bits) - 1
+    }
+}
+
 impl Type {
-    pub fn layout(&self, module: &PlayoutModule) -> std::alloc::Layout {
+    /// Follow type aliases until reaching a type that isn't an alias.
+    pub fn resolve<'a>(&'a self, module: &'a PlayoutModule) -> &'a Type {
         match self {
-            Type::Array { ty, size } => ty.layout(module).repeat(*size).unwrap().0,
-            Type::Primitive(ty) => ty.layout(),
-            Type::Path(path) => {
-                let data_struct = module.data_structs.get(path).unwrap();
-                data_struct.layout(module)
-            }
-            Type::Slice { .. } => panic!(),
+            Type::Path(path) => match module.type_aliases.get(path) {
+                Some(alias) => alias.ty.resolve(module),
+                None => self,
+            },
+            _ => self,
+        }
+    }
+
+    /// The named type of the elements of arrays and slices. Pointers have none, since their
+    /// pointees are declared separately, after the types that point to them.
+    pub fn base_nonprimitive_type(&self) -> Option<&String> {
+        match self {
+            Type::Primitive(_) | Type::Bitfield { .. } | Type::Ptr { .. } => None,
+            Type::Array { ty, .. } | Type::Slice { ty } => ty.base_nonprimitive_type(),
+            Type::Path(path) => Some(path),
+        }
+    }
+
+    /// The type a pointer points to, looking through arrays of pointers. Pointers to slices
+    /// give the element type.
+    pub fn pointee(&self) -> Option<&Type> {
+        match self {
+            Type::Ptr { ty } => match ty.as_ref() {
+                Type::Slice { ty } => Some(ty),
+                ty => Some(ty),
+            },
+            Type::Array { ty, .. } | Type::Slice { ty } => ty.pointee(),
+            _ => None,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrimitiveTypeSingle {
     U8,
     U16,
@@ -227,9 +913,29 @@ impl PrimitiveTypeSingle {
             F16 => std::alloc::Layout::new::<u16>(),
             F32 => std::alloc::Layout::new::<f32>(),
             F64 => std::alloc::Layout::new::<f64>(),
-            Bool => std::alloc::Layout::new::<bool>(),
+            // Booleans are 32 bits wide in shader interfaces
+            Bool => std::alloc::Layout::new::<u32>(),
         }
     }
+    pub fn is_integer(&self) -> bool {
+        use PrimitiveTypeSingle::*;
+        matches!(self, U8 | U16 | U32 | U64 | I8 | I16 | I32 | I64)
+    }
+    /// Values representable by an integer type, clamped to `i64`.
+    pub fn integer_range(&self) -> Option<std::ops::RangeInclusive<i64>> {
+        use PrimitiveTypeSingle::*;
+        Some(match self {
+            U8 => 0..=u8::MAX as i64,
+            U16 => 0..=u16::MAX as i64,
+            U32 => 0..=u32::MAX as i64,
+            U64 => 0..=i64::MAX,
+            I8 => i8::MIN as i64..=i8::MAX as i64,
+            I16 => i16::MIN as i64..=i16::MAX as i64,
+            I32 => i32::MIN as i64..=i32::MAX as i64,
+            I64 => i64::MIN..=i64::MAX,
+            _ => return None,
+        })
+    }
 }
 
 pub enum PrimitiveType {
@@ -244,18 +950,3 @@ pub enum PrimitiveType {
         columns: u8,
     },
 }
-impl PrimitiveType {
-    pub fn layout(&self) -> std::alloc::Layout {
-        match self {
-            PrimitiveType::Single(ty) => ty.layout(),
-            PrimitiveType::Vec { ty, length } => {
-                let layout = ty.layout();
-                layout.repeat(*length as usize).unwrap().0
-            }
-            PrimitiveType::Mat { ty, rows, columns } => {
-                let layout = ty.layout();
-                layout.repeat(*rows as usize * *columns as usize).unwrap().0
-            }
-        }
-    }
-}
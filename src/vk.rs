@@ -0,0 +1,375 @@
+use ash::vk;
+
+use crate::{
+    Binding, DescriptorAccess, DescriptorBufferWriter, DescriptorSizes, DescriptorType,
+    DeviceFeatures, ShaderStages,
+};
+
+/// Synchronization scope of the resources bound to a binding, for building
+/// `vk::BufferMemoryBarrier2` and `vk::ImageMemoryBarrier2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindingBarrier {
+    pub stages: vk::PipelineStageFlags2,
+    pub access: vk::AccessFlags2,
+    /// The layout images must be in when accessed through this binding. `None` for buffers.
+    pub image_layout: Option<vk::ImageLayout>,
+}
+
+impl ShaderStages {
+    pub fn to_vk(&self) -> vk::ShaderStageFlags {
+        vk::ShaderStageFlags::from_raw(self.bits())
+    }
+    pub fn to_pipeline_stages(&self) -> vk::PipelineStageFlags2 {
+        let mut stages = vk::PipelineStageFlags2::empty();
+        for (flag, stage) in [
+            (ShaderStages::VERTEX, vk::PipelineStageFlags2::VERTEX_SHADER),
+            (
+                ShaderStages::TELLESLATION_CONTROL,
+                vk::PipelineStageFlags2::TESSELLATION_CONTROL_SHADER,
+            ),
+            (
+                ShaderStages::TELLESLATION_EVALUATION,
+                vk::PipelineStageFlags2::TESSELLATION_EVALUATION_SHADER,
+            ),
+            (
+                ShaderStages::GEOMETRY,
+                vk::PipelineStageFlags2::GEOMETRY_SHADER,
+            ),
+            (
+                ShaderStages::FRAGMENT,
+                vk::PipelineStageFlags2::FRAGMENT_SHADER,
+            ),
+            (
+                ShaderStages::COMPUTE,
+                vk::PipelineStageFlags2::COMPUTE_SHADER,
+            ),
+            (ShaderStages::TASK, vk::PipelineStageFlags2::TASK_SHADER_EXT),
+            (ShaderStages::MESH, vk::PipelineStageFlags2::MESH_SHADER_EXT),
+            (
+                ShaderStages::RAYGEN
+                    | ShaderStages::ANY_HIT
+                    | ShaderStages::CLOSEST_HIT
+                    | ShaderStages::MISS
+                    | ShaderStages::INTERSECTION
+                    | ShaderStages::CALLABLE,
+                vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+            ),
+        ] {
+            if self.intersects(flag) {
+                stages |= stage;
+            }
+        }
+        stages
+    }
+}
+
+impl Binding {
+    pub fn barrier(&self) -> BindingBarrier {
+        let access = self.access();
+        let storage_access = {
+            let mut flags = vk::AccessFlags2::empty();
+            if access.contains(DescriptorAccess::READ) {
+                flags |= vk::AccessFlags2::SHADER_STORAGE_READ;
+            }
+            if access.contains(DescriptorAccess::WRITE) {
+                flags |= vk::AccessFlags2::SHADER_STORAGE_WRITE;
+            }
+            flags
+        };
+        let (access, image_layout) = descriptor_scope(&self.descriptor_type, storage_access);
+        BindingBarrier {
+            stages: self.stages.to_pipeline_stages(),
+            access,
+            image_layout,
+        }
+    }
+}
+
+/// Access and image layout of the resources bound to a descriptor.
+fn descriptor_scope(
+    descriptor_type: &DescriptorType,
+    storage_access: vk::AccessFlags2,
+) -> (vk::AccessFlags2, Option<vk::ImageLayout>) {
+    match descriptor_type {
+        DescriptorType::Sampler { .. } => (vk::AccessFlags2::empty(), None),
+        DescriptorType::StorageImage { .. } => (storage_access, Some(vk::ImageLayout::GENERAL)),
+        DescriptorType::SampledImage { .. }
+        | DescriptorType::CombinedImageSampler { shadow: false, .. } => (
+            vk::AccessFlags2::SHADER_SAMPLED_READ,
+            Some(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+        ),
+        DescriptorType::CombinedImageSampler { shadow: true, .. } => (
+            vk::AccessFlags2::SHADER_SAMPLED_READ,
+            Some(vk::ImageLayout::DEPTH_READ_ONLY_OPTIMAL),
+        ),
+        DescriptorType::UniformTexelBuffer { .. } => (vk::AccessFlags2::SHADER_SAMPLED_READ, None),
+        DescriptorType::StorageTexelBuffer { .. }
+        | DescriptorType::StorageBuffer { .. }
+        | DescriptorType::StorageBufferDynamic { .. } => (storage_access, None),
+        DescriptorType::UniformBuffer { .. }
+        | DescriptorType::UniformBufferDynamic { .. }
+        | DescriptorType::InlineUniformBlock { .. } => (vk::AccessFlags2::UNIFORM_READ, None),
+        DescriptorType::InputAttachment { .. } => (
+            vk::AccessFlags2::INPUT_ATTACHMENT_READ,
+            Some(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL),
+        ),
+        DescriptorType::AccelerationStructure => {
+            (vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR, None)
+        }
+        // Images that may be viewed as storage images must stay in the general layout.
+        DescriptorType::Mutable { types } => types.iter().fold(
+            (vk::AccessFlags2::empty(), None),
+            |(access, layout), member| {
+                let (member_access, member_layout) = descriptor_scope(member, storage_access);
+                let layout = match (layout, member_layout) {
+                    (Some(vk::ImageLayout::GENERAL), _) | (_, Some(vk::ImageLayout::GENERAL)) => {
+                        Some(vk::ImageLayout::GENERAL)
+                    }
+                    (layout, member_layout) => layout.or(member_layout),
+                };
+                (access | member_access, layout)
+            },
+        ),
+    }
+}
+
+impl From<&vk::PhysicalDeviceDescriptorBufferPropertiesEXT<'_>> for DescriptorSizes {
+    fn from(properties: &vk::PhysicalDeviceDescriptorBufferPropertiesEXT<'_>) -> Self {
+        Self {
+            sampler: properties.sampler_descriptor_size,
+            combined_image_sampler: properties.combined_image_sampler_descriptor_size,
+            sampled_image: properties.sampled_image_descriptor_size,
+            storage_image: properties.storage_image_descriptor_size,
+            uniform_texel_buffer: properties.uniform_texel_buffer_descriptor_size,
+            storage_texel_buffer: properties.storage_texel_buffer_descriptor_size,
+            uniform_buffer: properties.uniform_buffer_descriptor_size,
+            storage_buffer: properties.storage_buffer_descriptor_size,
+            input_attachment: properties.input_attachment_descriptor_size,
+            acceleration_structure: properties.acceleration_structure_descriptor_size,
+        }
+    }
+}
+
+impl DescriptorBufferWriter<'_> {
+    /// Write descriptor `element` of the binding named `name` with `vkGetDescriptorEXT`.
+    /// `info.ty` must match the descriptor type of the binding.
+    ///
+    /// # Safety
+    /// `info` must be valid for `vkGetDescriptorEXT`.
+    pub unsafe fn write(
+        &mut self,
+        device: &ash::ext::descriptor_buffer::Device,
+        name: &str,
+        element: u32,
+        info: &vk::DescriptorGetInfoEXT<'_>,
+    ) {
+        let Some(binding) = self
+            .set
+            .bindings
+            .iter()
+            .find(|binding| binding.ident == name)
+        else {
+            panic!("No binding {} in set {}", name, self.set.name);
+        };
+        assert!(
+            info.ty == binding.descriptor_type.to_vk(),
+            "Descriptor type mismatch for {}",
+            name
+        );
+        let Some(dst) = self.descriptor_mut(name, element) else {
+            panic!(
+                "No descriptor {}[{}] in set {}",
+                name, element, self.set.name
+            );
+        };
+        device.get_descriptor(info, dst);
+    }
+}
+
+impl DeviceFeatures {
+    /// The features a device supports, as returned by `vkGetPhysicalDeviceFeatures2`. Reads the
+    /// Vulkan 1.1, 1.2 and 1.3 feature structs and those of the acceleration structure, ray
+    /// tracing pipeline, ray query, mutable descriptor type and mesh shader extensions from the
+    /// `p_next` chain.
+    ///
+    /// # Safety
+    /// The `p_next` chain of `features` must be valid.
+    pub unsafe fn from_vk(features: &vk::PhysicalDeviceFeatures2<'_>) -> Self {
+        let flag = |enabled: vk::Bool32, flag: DeviceFeatures| {
+            if enabled == vk::TRUE {
+                flag
+            } else {
+                DeviceFeatures::empty()
+            }
+        };
+        let core = &features.features;
+        let mut supported = flag(
+            core.shader_storage_image_read_without_format,
+            DeviceFeatures::SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT,
+        ) | flag(
+            core.shader_storage_image_write_without_format,
+            DeviceFeatures::SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT,
+        ) | flag(
+            core.shader_storage_image_multisample,
+            DeviceFeatures::SHADER_STORAGE_IMAGE_MULTISAMPLE,
+        ) | flag(core.image_cube_array, DeviceFeatures::IMAGE_CUBE_ARRAY)
+            | flag(core.geometry_shader, DeviceFeatures::GEOMETRY_SHADER)
+            | flag(
+                core.tessellation_shader,
+                DeviceFeatures::TESSELLATION_SHADER,
+            )
+            | flag(
+                core.fragment_stores_and_atomics,
+                DeviceFeatures::FRAGMENT_STORES_AND_ATOMICS,
+            )
+            | flag(
+                core.vertex_pipeline_stores_and_atomics,
+                DeviceFeatures::VERTEX_PIPELINE_STORES_AND_ATOMICS,
+            )
+            | flag(core.sampler_anisotropy, DeviceFeatures::SAMPLER_ANISOTROPY)
+            | flag(core.shader_float64, DeviceFeatures::SHADER_FLOAT64)
+            | flag(core.shader_int64, DeviceFeatures::SHADER_INT64)
+            | flag(core.shader_int16, DeviceFeatures::SHADER_INT16);
+
+        let mut next = features.p_next as *const vk::BaseOutStructure<'_>;
+        while let Some(header) = next.as_ref() {
+            match header.s_type {
+                vk::StructureType::PHYSICAL_DEVICE_VULKAN_1_1_FEATURES => {
+                    let features = &*next.cast::<vk::PhysicalDeviceVulkan11Features<'_>>();
+                    supported |= flag(
+                        features.storage_buffer16_bit_access,
+                        DeviceFeatures::STORAGE_BUFFER_16BIT_ACCESS,
+                    ) | flag(
+                        features.uniform_and_storage_buffer16_bit_access,
+                        DeviceFeatures::UNIFORM_AND_STORAGE_BUFFER_16BIT_ACCESS,
+                    ) | flag(
+                        features.storage_push_constant16,
+                        DeviceFeatures::STORAGE_PUSH_CONSTANT_16,
+                    );
+                }
+                vk::StructureType::PHYSICAL_DEVICE_VULKAN_1_2_FEATURES => {
+                    let features = &*next.cast::<vk::PhysicalDeviceVulkan12Features<'_>>();
+                    supported |= flag(
+                        features.sampler_mirror_clamp_to_edge,
+                        DeviceFeatures::SAMPLER_MIRROR_CLAMP_TO_EDGE,
+                    ) | flag(
+                        features.storage_buffer8_bit_access,
+                        DeviceFeatures::STORAGE_BUFFER_8BIT_ACCESS,
+                    ) | flag(
+                        features.uniform_and_storage_buffer8_bit_access,
+                        DeviceFeatures::UNIFORM_AND_STORAGE_BUFFER_8BIT_ACCESS,
+                    ) | flag(
+                        features.storage_push_constant8,
+                        DeviceFeatures::STORAGE_PUSH_CONSTANT_8,
+                    ) | flag(features.shader_float16, DeviceFeatures::SHADER_FLOAT16)
+                        | flag(features.shader_int8, DeviceFeatures::SHADER_INT8)
+                        | flag(
+                            features.runtime_descriptor_array,
+                            DeviceFeatures::RUNTIME_DESCRIPTOR_ARRAY,
+                        )
+                        | flag(
+                            features.descriptor_binding_partially_bound,
+                            DeviceFeatures::DESCRIPTOR_BINDING_PARTIALLY_BOUND,
+                        )
+                        | flag(
+                            features.descriptor_binding_variable_descriptor_count,
+                            DeviceFeatures::DESCRIPTOR_BINDING_VARIABLE_DESCRIPTOR_COUNT,
+                        )
+                        | flag(
+                            features.descriptor_binding_update_unused_while_pending,
+                            DeviceFeatures::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING,
+                        )
+                        | flag(
+                            features.descriptor_binding_uniform_buffer_update_after_bind,
+                            DeviceFeatures::DESCRIPTOR_BINDING_UNIFORM_BUFFER_UPDATE_AFTER_BIND,
+                        )
+                        | flag(
+                            features.descriptor_binding_sampled_image_update_after_bind,
+                            DeviceFeatures::DESCRIPTOR_BINDING_SAMPLED_IMAGE_UPDATE_AFTER_BIND,
+                        )
+                        | flag(
+                            features.descriptor_binding_storage_image_update_after_bind,
+                            DeviceFeatures::DESCRIPTOR_BINDING_STORAGE_IMAGE_UPDATE_AFTER_BIND,
+                        )
+                        | flag(
+                            features.descriptor_binding_storage_buffer_update_after_bind,
+                            DeviceFeatures::DESCRIPTOR_BINDING_STORAGE_BUFFER_UPDATE_AFTER_BIND,
+                        )
+                        | flag(
+                            features.descriptor_binding_uniform_texel_buffer_update_after_bind,
+                            DeviceFeatures::DESCRIPTOR_BINDING_UNIFORM_TEXEL_BUFFER_UPDATE_AFTER_BIND,
+                        )
+                        | flag(
+                            features.descriptor_binding_storage_texel_buffer_update_after_bind,
+                            DeviceFeatures::DESCRIPTOR_BINDING_STORAGE_TEXEL_BUFFER_UPDATE_AFTER_BIND,
+                        )
+                        | flag(
+                            features.scalar_block_layout,
+                            DeviceFeatures::SCALAR_BLOCK_LAYOUT,
+                        )
+                        | flag(
+                            features.buffer_device_address,
+                            DeviceFeatures::BUFFER_DEVICE_ADDRESS,
+                        );
+                }
+                vk::StructureType::PHYSICAL_DEVICE_VULKAN_1_3_FEATURES => {
+                    let features = &*next.cast::<vk::PhysicalDeviceVulkan13Features<'_>>();
+                    supported |= flag(
+                        features.inline_uniform_block,
+                        DeviceFeatures::INLINE_UNIFORM_BLOCK,
+                    ) | flag(
+                        features.descriptor_binding_inline_uniform_block_update_after_bind,
+                        DeviceFeatures::DESCRIPTOR_BINDING_INLINE_UNIFORM_BLOCK_UPDATE_AFTER_BIND,
+                    );
+                }
+                vk::StructureType::PHYSICAL_DEVICE_ACCELERATION_STRUCTURE_FEATURES_KHR => {
+                    let features =
+                        &*next.cast::<vk::PhysicalDeviceAccelerationStructureFeaturesKHR<'_>>();
+                    supported |= flag(
+                        features.acceleration_structure,
+                        DeviceFeatures::ACCELERATION_STRUCTURE,
+                    ) | flag(
+                        features.descriptor_binding_acceleration_structure_update_after_bind,
+                        DeviceFeatures::DESCRIPTOR_BINDING_ACCELERATION_STRUCTURE_UPDATE_AFTER_BIND,
+                    );
+                }
+                vk::StructureType::PHYSICAL_DEVICE_RAY_TRACING_PIPELINE_FEATURES_KHR => {
+                    let features =
+                        &*next.cast::<vk::PhysicalDeviceRayTracingPipelineFeaturesKHR<'_>>();
+                    supported |= flag(
+                        features.ray_tracing_pipeline,
+                        DeviceFeatures::RAY_TRACING_PIPELINE,
+                    );
+                }
+                vk::StructureType::PHYSICAL_DEVICE_RAY_QUERY_FEATURES_KHR => {
+                    let features = &*next.cast::<vk::PhysicalDeviceRayQueryFeaturesKHR<'_>>();
+                    supported |= flag(features.ray_query, DeviceFeatures::RAY_QUERY);
+                }
+                vk::StructureType::PHYSICAL_DEVICE_MUTABLE_DESCRIPTOR_TYPE_FEATURES_EXT => {
+                    let features =
+                        &*next.cast::<vk::PhysicalDeviceMutableDescriptorTypeFeaturesEXT<'_>>();
+                    supported |= flag(
+                        features.mutable_descriptor_type,
+                        DeviceFeatures::MUTABLE_DESCRIPTOR_TYPE,
+                    );
+                }
+                vk::StructureType::PHYSICAL_DEVICE_MESH_SHADER_FEATURES_EXT => {
+                    let features = &*next.cast::<vk::PhysicalDeviceMeshShaderFeaturesEXT<'_>>();
+                    supported |= flag(features.task_shader, DeviceFeatures::TASK_SHADER)
+                        | flag(features.mesh_shader, DeviceFeatures::MESH_SHADER);
+                }
+                vk::StructureType::PHYSICAL_DEVICE_SHADER_IMAGE_ATOMIC_INT64_FEATURES_EXT => {
+                    let features =
+                        &*next.cast::<vk::PhysicalDeviceShaderImageAtomicInt64FeaturesEXT<'_>>();
+                    supported |= flag(
+                        features.shader_image_int64_atomics,
+                        DeviceFeatures::SHADER_IMAGE_INT64_ATOMICS,
+                    );
+                }
+                _ => (),
+            }
+            next = header.p_next;
+        }
+        supported
+    }
+}